@@ -0,0 +1,58 @@
+/*
+ * File: ascii.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use clap::{ArgMatches, App, Arg};
+use p50x::{P50XAscii, P50XReply};
+
+use crate::utils::{common_command, run_command_with_result};
+
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    let command: Vec<_> = matches.values_of("command").unwrap().collect();
+    let command = command.join(" ");
+
+    return run_command_with_result(
+        matches,
+        |device| device.acommand(&command)?.into_lines(),
+        |lines| {
+            if lines.is_empty() {
+                return Ok(P50XReply::Ok.to_string());
+            }
+
+            return Ok(lines.join("\n"));
+        }
+    );
+}
+
+pub fn command<'a>() -> App<'a, 'a> {
+    common_command("ascii", "Send a textual P50Xa command")
+        .arg(Arg::with_name("command")
+            .help("Command with its parameters, e.g. L 3, 20, 1, f")
+            .required(true)
+            .takes_value(true)
+            .multiple(true))
+}
@@ -0,0 +1,98 @@
+/*
+ * File: cv.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use clap::{ArgMatches, App, Arg};
+use p50x::ProgrammingOnMain;
+
+use crate::utils::{command_group, common_command, invalid_argument, run_command_with_result, str_to_bool};
+
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    if let ("pom", Some(m)) = matches.subcommand() {
+        run_command_with_result(
+            m,
+            |device| {
+                let address = match m.value_of("address").unwrap().parse::<u16>() {
+                    Ok(address) => address,
+                    Err(_) => return Err(invalid_argument(m, "address"))
+                };
+
+                let cv = match m.value_of("cv").unwrap().parse::<u16>() {
+                    Ok(cv) => cv,
+                    Err(_) => return Err(invalid_argument(m, "cv"))
+                };
+
+                // read the value if none is given
+                let value = match m.value_of("value") {
+                    Some(value) => value,
+                    None => return device.pom_read(address, cv).map(|value| value.to_string())
+                };
+
+                if let Some(bit) = m.value_of("bit") {
+                    return match bit.parse::<u8>() {
+                        Ok(bit) => device.pom_write_bit(address, cv, bit, str_to_bool(value)).map(|_| "Ok".to_string()),
+                        Err(_) => Err(invalid_argument(m, "bit"))
+                    };
+                }
+
+                match value.parse::<u8>() {
+                    Ok(value) => device.pom_write(address, cv, value).map(|_| "Ok".to_string()),
+                    Err(_) => Err(invalid_argument(m, "value"))
+                }
+            },
+            Ok
+        )?;
+    }
+
+    return Ok(());
+}
+
+pub fn command<'a>() -> App<'a, 'a> {
+    command_group(
+        "cv",
+        "Read and write decoder configuration variables",
+        vec![
+            common_command("pom", "Read or write a configuration variable on the main track")
+                .arg(Arg::with_name("address")
+                    .help("Locomotive address")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("cv")
+                    .help("Configuration variable number")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("value")
+                    .help("Value to write, reads the value if omitted")
+                    .takes_value(true))
+                .arg(Arg::with_name("bit")
+                    .help("Write only the given bit from 0 to 7")
+                    .long("bit")
+                    .takes_value(true)
+                    .requires("value"))
+        ]
+    )
+}
@@ -26,11 +26,10 @@
  * SOFTWARE.
  */
 
-use clap::{ArgMatches, App};
+use clap::{ArgMatches, App, Arg};
 use p50x::P50XBinary;
-use serial_unit_testing::utils::{radix_string, TextFormat};
 
-use crate::utils::{command_group, common_command, run_command, run_command_with_result};
+use crate::utils::{command_group, common_command, get_unverified_device, run_command, run_command_with_result};
 
 pub fn run(matches: &ArgMatches) -> Result<(), String> {
     match matches.subcommand() {
@@ -38,34 +37,25 @@ pub fn run(matches: &ArgMatches) -> Result<(), String> {
         ("version", Some(m)) => run_command_with_result(
             m,
             |device| device.xversion(),
-            |result| {
-                let mut version = String::new();
-                let mut index = 0;
-
-                loop {
-                    let length = result[index] as usize;
-                    if length == 0 {
-                        break;
-                    }
-
-                    let data = &result[index + 1..index + 1 + length];
-                    version.push_str("0x");
-
-                    match radix_string(data, &TextFormat::Hex) {
-                        Ok(hex_string) => version.push_str(&hex_string),
-                        Err(err) => return Err(format!("Unable to format version: {}", err.to_string()))
-                    };
-
-                    index += 1 + length;
+            |result| match result.capabilities() {
+                Some(capabilities) => Ok(format!("{}\n\nCapabilities:\n{}", result, capabilities)),
+                None => Ok(format!("{}\n\nCapabilities: unknown", result))
+            }
+        )?,
+        ("nop", Some(m)) => run_command(m, |device| device.xnop())?,
+        ("capabilities", Some(m)) => run_command_with_result(m, |device| device.probe_capabilities(), |result| Ok(result.to_string()))?,
+        ("detect", Some(m)) => {
+            let mut device = get_unverified_device(m)?;
 
-                    if index < result.len() - 1 {
-                        version.push('\n');
-                    }
-                }
+            let info = match device.negotiate(m.is_present("switch")) {
+                Ok(info) => info,
+                Err(err) => return Err(err.to_string())
+            };
 
-                return Ok(version);
-            })?,
-        ("nop", Some(m)) => run_command(m, |device| device.xnop())?,
+            if m.is_present("quiet") == false {
+                println!("{}", info);
+            }
+        },
         _ => ()
     };
 
@@ -79,7 +69,13 @@ pub fn command<'a>() -> App<'a, 'a> {
         vec![
             common_command("status", "Get device current status"),
             common_command("version", "Get device version"),
-            common_command("nop", "No operation command")
+            common_command("nop", "No operation command"),
+            common_command("capabilities", "Probe which optional commands the device supports"),
+            common_command("detect", "Detect the protocol mode and extended character of the device")
+                .arg(Arg::with_name("switch")
+                    .help("Switch a device in ASCII mode to binary commands")
+                    .long("switch")
+                    .short("s"))
         ]
     )
 }
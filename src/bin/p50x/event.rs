@@ -0,0 +1,69 @@
+/*
+ * File: event.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use clap::{ArgMatches, App};
+use p50x::P50XBinary;
+
+use crate::utils::{command_group, common_command, run_command_with_result};
+
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    match matches.subcommand() {
+        ("status", Some(m)) => run_command_with_result(m, |device| device.xevent(), |result| Ok(result.to_string()))?,
+        ("loco", Some(m)) => run_command_with_result(m, |device| device.xevt_lok(), |result| Ok(join_events(&result)))?,
+        ("turnout", Some(m)) => run_command_with_result(m, |device| device.xevt_turnout(), |result| Ok(join_events(&result)))?,
+        ("sensor", Some(m)) => run_command_with_result(m, |device| device.xevt_sensor(), |result| Ok(join_events(&result)))?,
+        _ => ()
+    };
+
+    return Ok(());
+}
+
+pub fn command<'a>() -> App<'a, 'a> {
+    command_group(
+        "event",
+        "Get pending events of the device",
+        vec![
+            common_command("status", "Get which kind of events are pending"),
+            common_command("loco", "Get pending locomotive events"),
+            common_command("turnout", "Get pending turnout events"),
+            common_command("sensor", "Get pending sensor events")
+        ]
+    )
+}
+
+fn join_events<T: ToString>(events: &[T]) -> String {
+    if events.is_empty() {
+        return "No events".to_string();
+    }
+
+    events
+        .iter()
+        .map(|event| event.to_string())
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
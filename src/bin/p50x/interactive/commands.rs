@@ -7,10 +7,12 @@
  */
 
 use std::collections::HashMap;
-use p50x::{Device, P50XBinary};
+use p50x::P50XBinary;
 
-pub fn register_commands() -> HashMap<String, fn(&str, &mut Device) -> Result<(), p50x::Error>> {
-    let mut commands: HashMap<String, fn(&str, &mut Device) -> Result<(), p50x::Error>> = HashMap::new();
+use crate::utils::AnyDevice;
+
+pub fn register_commands() -> HashMap<String, fn(&str, &mut AnyDevice) -> Result<(), p50x::Error>> {
+    let mut commands: HashMap<String, fn(&str, &mut AnyDevice) -> Result<(), p50x::Error>> = HashMap::new();
 
     commands.insert("power on".into(), |_, device| device.xpower_on());
     commands.insert("power off".into(), |_, device| device.xpower_off());
@@ -80,7 +80,7 @@ pub fn command<'a>() -> App<'a, 'a> {
         .about("Interactive mode to send multiple commands to device")
         .args(&vec![
             Arg::with_name("port")
-                .help("Serial port OS specific name")
+                .help("Serial port OS specific name or tcp://host:port")
                 .required(true)
                 .takes_value(true),
             Arg::with_name("baud")
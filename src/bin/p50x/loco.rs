@@ -27,18 +27,18 @@
  */
 
 use clap::{ArgMatches, App, Arg};
-use p50x::{P50XBinary, Error, XLokOptions, bool_arr_to_string};
+use p50x::{P50XBinary, LokFunctions, Error, XProtocol, XLokOptions, bool_arr_to_string};
 
-use crate::utils::{command_group, common_command, run_command, run_command_with_result, str_to_bool};
+use crate::utils::{command_group, common_command, invalid_argument, run_command_with_outcome, run_command_with_result, str_to_bool};
 
 pub fn run(matches: &ArgMatches) -> Result<(), String> {
     match matches.subcommand() {
-        ("set", Some(m)) => run_command(
+        ("set", Some(m)) => run_command_with_outcome(
             m,
             |device| {
                 let address = match m.value_of("address").unwrap().parse::<u16>() {
                     Ok(so) => so,
-                    Err(_) => return Err(Error::Other) // TODO: Proper handle parse error
+                    Err(_) => return Err(invalid_argument(m, "address"))
                 };
 
                 let functions = if m.is_present("functions") {
@@ -58,12 +58,13 @@ pub fn run(matches: &ArgMatches) -> Result<(), String> {
                     light: m.is_present("light"),
                     emergency_stop: m.is_present("emergency-stop"),
                     force: m.is_present("force"),
+                    reverse: false,
                     functions
                 };
 
                 match m.value_of("speed").unwrap().parse::<i8>() {
                     Ok(speed) => device.xlok(address, speed, options),
-                    Err(_) => Err(Error::Other) // TODO: Proper handle parse error
+                    Err(_) => Err(invalid_argument(m, "speed"))
                 }
             }
         )?,
@@ -72,7 +73,7 @@ pub fn run(matches: &ArgMatches) -> Result<(), String> {
             |device| {
                 match m.value_of("address").unwrap().parse::<u16>() {
                     Ok(address) => device.xlok_status(address),
-                    Err(_) => return Err(Error::Other) // TODO: Proper handle parse error
+                    Err(_) => return Err(invalid_argument(m, "address"))
                 }
             },
             |result| Ok(result.to_string())
@@ -80,14 +81,46 @@ pub fn run(matches: &ArgMatches) -> Result<(), String> {
         ("config", Some(m)) => run_command_with_result(
             m,
             |device| {
-                match m.value_of("address").unwrap().parse::<u16>() {
-                    Ok(address) => device.xlok_config(address),
-                    Err(_) => return Err(Error::Other) // TODO: Proper handle parse error
+                let address = match m.value_of("address").unwrap().parse::<u16>() {
+                    Ok(address) => address,
+                    Err(_) => return Err(invalid_argument(m, "address"))
+                };
+
+                if m.is_present("set") == false {
+                    return device.xlok_config(address);
+                }
+
+                // keep all values which are not given
+                let mut config = device.xlok_config(address)?;
+
+                if let Some(protocol) = m.value_of("protocol") {
+                    config.protocol = parse_protocol(protocol)?;
+                }
+
+                if let Some(speed_steps) = m.value_of("speed-steps") {
+                    config.speed_steps = match speed_steps.parse::<u8>() {
+                        Ok(speed_steps) => speed_steps,
+                        Err(_) => return Err(invalid_argument(m, "speed-steps"))
+                    };
+                }
+
+                if let Some(virtual_address) = m.value_of("virtual-address") {
+                    config.virtual_address = match virtual_address {
+                        "none" => None,
+                        value => match value.parse::<u16>() {
+                            Ok(virtual_address) => Some(virtual_address),
+                            Err(_) => return Err(invalid_argument(m, "virtual-address"))
+                        }
+                    };
                 }
+
+                device.xlok_set_config(address, config)?;
+
+                return device.xlok_config(address);
             },
             |result| Ok(result.to_string())
         )?,
-        ("func", Some(m)) => run_command(
+        ("func", Some(m)) => run_command_with_outcome(
             m,
             |device| {
                 let function_values: Vec<_> = m.values_of("functions").unwrap().collect();
@@ -99,7 +132,7 @@ pub fn run(matches: &ArgMatches) -> Result<(), String> {
 
                 match m.value_of("address").unwrap().parse::<u16>() {
                     Ok(address) => device.xfunc(address, values),
-                    Err(_) => return Err(Error::Other) // TODO: Proper handle parse error
+                    Err(_) => return Err(invalid_argument(m, "address"))
                 }
             }
         )?,
@@ -108,12 +141,12 @@ pub fn run(matches: &ArgMatches) -> Result<(), String> {
             |device| {
                 match m.value_of("address").unwrap().parse::<u16>() {
                     Ok(address) => device.xfunc_status(address),
-                    Err(_) => return Err(Error::Other) // TODO: Proper handle parse error
+                    Err(_) => return Err(invalid_argument(m, "address"))
                 }
             },
             |result| Ok(bool_arr_to_string(&result))
         )?,
-        ("funcx", Some(m)) => run_command(
+        ("funcx", Some(m)) => run_command_with_outcome(
             m,
             |device| {
                 let function_values: Vec<_> = m.values_of("functions").unwrap().collect();
@@ -125,7 +158,7 @@ pub fn run(matches: &ArgMatches) -> Result<(), String> {
 
                 match m.value_of("address").unwrap().parse::<u16>() {
                     Ok(address) => device.xfuncx(address, values),
-                    Err(_) => return Err(Error::Other) // TODO: Proper handle parse error
+                    Err(_) => return Err(invalid_argument(m, "address"))
                 }
             }
         )?,
@@ -134,11 +167,77 @@ pub fn run(matches: &ArgMatches) -> Result<(), String> {
             |device| {
                 match m.value_of("address").unwrap().parse::<u16>() {
                     Ok(address) => device.xfuncx_status(address),
-                    Err(_) => return Err(Error::Other) // TODO: Proper handle parse error
+                    Err(_) => return Err(invalid_argument(m, "address"))
                 }
             },
             |result| Ok(bool_arr_to_string(&result))
         )?,
+        ("func34", Some(m)) => run_command_with_outcome(
+            m,
+            |device| {
+                let function_values: Vec<_> = m.values_of("functions").unwrap().collect();
+                let mut values: [bool; 16] = [false; 16];
+
+                for i in 0..12 {
+                    values[i] = str_to_bool(function_values[i]);
+                }
+
+                match m.value_of("address").unwrap().parse::<u16>() {
+                    Ok(address) => device.xfunc34(address, values),
+                    Err(_) => return Err(invalid_argument(m, "address"))
+                }
+            }
+        )?,
+        ("func34-status", Some(m)) => run_command_with_result(
+            m,
+            |device| {
+                match m.value_of("address").unwrap().parse::<u16>() {
+                    Ok(address) => device.xfunc34_status(address),
+                    Err(_) => return Err(invalid_argument(m, "address"))
+                }
+            },
+            |result| Ok(bool_arr_to_string(&result[..12]))
+        )?,
+        ("function", Some(m)) => run_command_with_outcome(
+            m,
+            |device| {
+                let address = match m.value_of("address").unwrap().parse::<u16>() {
+                    Ok(address) => address,
+                    Err(_) => return Err(invalid_argument(m, "address"))
+                };
+
+                let state = str_to_bool(m.value_of("state").unwrap());
+
+                let options = XLokOptions {
+                    force: m.is_present("force"),
+                    ..Default::default()
+                };
+
+                match m.value_of("function").unwrap().parse::<u8>() {
+                    Ok(0) => device.set_light(address, state, options),
+                    Ok(function) => device.set_function(address, function, state),
+                    Err(_) => Err(invalid_argument(m, "function"))
+                }
+            }
+        )?,
+        ("functions", Some(m)) => run_command_with_result(
+            m,
+            |device| {
+                match m.value_of("address").unwrap().parse::<u16>() {
+                    Ok(address) => device.functions(address),
+                    Err(_) => return Err(invalid_argument(m, "address"))
+                }
+            },
+            |result| {
+                let lines: Vec<String> = result
+                    .iter()
+                    .enumerate()
+                    .map(|(i, state)| format!("F{}: {}", i, bool_arr_to_string(&[*state])))
+                    .collect();
+
+                return Ok(lines.join("\n"));
+            }
+        )?,
         _ => ()
     };
 
@@ -183,11 +282,34 @@ pub fn command<'a>() -> App<'a, 'a> {
                     .help("Locomotive address")
                     .required(true)
                     .takes_value(true)),
-            common_command("config", "Get locomotive configuration")
+            common_command("config", "Get or set locomotive configuration")
                 .arg(Arg::with_name("address")
                     .help("Locomotive address")
                     .required(true)
-                    .takes_value(true)),
+                    .takes_value(true))
+                .arg(Arg::with_name("set")
+                    .help("Change the configuration, values not given are kept")
+                    .long("set")
+                    .short("s"))
+                .arg(Arg::with_name("protocol")
+                    .help("Locomotive protocol")
+                    .long("protocol")
+                    .short("p")
+                    .takes_value(true)
+                    .requires("set")
+                    .possible_values(&["motorola", "selectrix", "dcc", "fmz"]))
+                .arg(Arg::with_name("speed-steps")
+                    .help("Number of speed steps")
+                    .long("speed-steps")
+                    .short("S")
+                    .takes_value(true)
+                    .requires("set"))
+                .arg(Arg::with_name("virtual-address")
+                    .help("Virtual address or none to remove it")
+                    .long("virtual-address")
+                    .short("V")
+                    .takes_value(true)
+                    .requires("set")),
             common_command("func", "Set locomotive first function group")
                 .arg(Arg::with_name("address")
                     .help("Locomotive address")
@@ -216,6 +338,44 @@ pub fn command<'a>() -> App<'a, 'a> {
                     .min_values(8)
                     .max_values(8)),
             common_command("funcx-status", "Get locomotive second function group")
+                .arg(Arg::with_name("address")
+                    .help("Locomotive address")
+                    .required(true)
+                    .takes_value(true)),
+            common_command("func34", "Set locomotive third and fourth function group")
+                .arg(Arg::with_name("address")
+                    .help("Locomotive address")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("functions")
+                    .help("Functions 17-28 to set")
+                    .takes_value(true)
+                    .required(true)
+                    .min_values(12)
+                    .max_values(12)),
+            common_command("func34-status", "Get locomotive third and fourth function group")
+                .arg(Arg::with_name("address")
+                    .help("Locomotive address")
+                    .required(true)
+                    .takes_value(true)),
+            common_command("function", "Set a single locomotive function and keep all others")
+                .arg(Arg::with_name("address")
+                    .help("Locomotive address")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("function")
+                    .help("Function number, 0 is the light")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("state")
+                    .help("Function state")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("force")
+                    .help("Force switching the light even if the loco is controlled elsewhere")
+                    .long("force")
+                    .short("f")),
+            common_command("functions", "Get all locomotive functions")
                 .arg(Arg::with_name("address")
                     .help("Locomotive address")
                     .required(true)
@@ -223,3 +383,13 @@ pub fn command<'a>() -> App<'a, 'a> {
         ]
     )
 }
+
+fn parse_protocol(value: &str) -> p50x::Result<XProtocol> {
+    match value {
+        "motorola" => Ok(XProtocol::Motorola),
+        "selectrix" => Ok(XProtocol::Selectrix),
+        "dcc" => Ok(XProtocol::DCC),
+        "fmz" => Ok(XProtocol::FMZ),
+        _ => Err(Error::InvalidArgument(format!("protocol '{}'", value)))
+    }
+}
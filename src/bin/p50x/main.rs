@@ -30,15 +30,22 @@
 extern crate clap;
 
 use std::process::exit;
-use clap::{App, ArgMatches, AppSettings};
+use clap::{App, Arg, ArgMatches, AppSettings};
 
 mod utils;
 mod power;
 mod device;
 mod so;
 mod loco;
+mod cv;
 mod turnout;
+mod event;
+mod pt;
 mod interactive;
+mod sim;
+mod ascii;
+mod trace;
+mod record;
 
 fn run(matches: ArgMatches) -> Result<(), String> {
     match matches.subcommand() {
@@ -46,8 +53,14 @@ fn run(matches: ArgMatches) -> Result<(), String> {
         ("device", Some(m)) => device::run(m),
         ("so", Some(m)) => so::run(m),
         ("loco", Some(m)) => loco::run(m),
+        ("cv", Some(m)) => cv::run(m),
         ("turnout", Some(m)) => turnout::run(m),
+        ("event", Some(m)) => event::run(m),
+        ("pt", Some(m)) => pt::run(m),
         ("interactive", Some(m)) => interactive::run(m),
+        ("sim", Some(m)) => sim::run(m),
+        ("ascii", Some(m)) => ascii::run(m),
+        ("record", Some(m)) => record::run(m),
         _ => Ok(())
     }
 }
@@ -59,16 +72,28 @@ fn main() {
         .version(crate_version!())
         .version_short("v")
         .about("P50X command-line utility")
+        .arg(Arg::with_name("trace")
+            .long("trace")
+            .global(true)
+            .help("Print all data exchanged with the device"))
         .subcommands(vec![
             power::command(),
             device::command(),
             so::command(),
             loco::command(),
+            cv::command(),
             turnout::command(),
-            interactive::command()
+            event::command(),
+            pt::command(),
+            interactive::command(),
+            sim::command(),
+            ascii::command(),
+            record::command()
         ])
         .get_matches();
 
+    trace::init(matches.is_present("trace"));
+
     if let Err(e) = run(matches) {
         eprintln!("{}", e);
 
@@ -0,0 +1,178 @@
+/*
+ * File: pt.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use clap::{ArgMatches, App, Arg};
+use p50x::{P50XBinary, ProgrammingTrack, ProgrammingMode, Error};
+
+use crate::utils::{command_group, common_command, invalid_argument, run_command, run_command_with_result, str_to_bool};
+
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    match matches.subcommand() {
+        ("read", Some(m)) => run_command_with_result(
+            m,
+            |device| {
+                let mode = parse_mode(m.value_of("mode").unwrap())?;
+
+                match m.value_of("cv").unwrap().parse::<u16>() {
+                    Ok(cv) => device.pt_read(mode, cv),
+                    Err(_) => Err(invalid_argument(m, "cv"))
+                }
+            },
+            |result| Ok(result.to_string())
+        )?,
+        ("write", Some(m)) => run_command(
+            m,
+            |device| {
+                let mode = parse_mode(m.value_of("mode").unwrap())?;
+
+                let cv = match m.value_of("cv").unwrap().parse::<u16>() {
+                    Ok(cv) => cv,
+                    Err(_) => return Err(invalid_argument(m, "cv"))
+                };
+
+                match m.value_of("value").unwrap().parse::<u8>() {
+                    Ok(value) => device.pt_write(mode, cv, value),
+                    Err(_) => Err(invalid_argument(m, "value"))
+                }
+            }
+        )?,
+        ("read-bit", Some(m)) => run_command_with_result(
+            m,
+            |device| {
+                let cv = match m.value_of("cv").unwrap().parse::<u16>() {
+                    Ok(cv) => cv,
+                    Err(_) => return Err(invalid_argument(m, "cv"))
+                };
+
+                match m.value_of("bit").unwrap().parse::<u8>() {
+                    Ok(bit) => device.pt_read_bit(cv, bit),
+                    Err(_) => Err(invalid_argument(m, "bit"))
+                }
+            },
+            |result| Ok(result.to_string())
+        )?,
+        ("write-bit", Some(m)) => run_command(
+            m,
+            |device| {
+                let cv = match m.value_of("cv").unwrap().parse::<u16>() {
+                    Ok(cv) => cv,
+                    Err(_) => return Err(invalid_argument(m, "cv"))
+                };
+
+                let value = str_to_bool(m.value_of("value").unwrap());
+
+                match m.value_of("bit").unwrap().parse::<u8>() {
+                    Ok(bit) => device.pt_write_bit(cv, bit, value),
+                    Err(_) => Err(invalid_argument(m, "bit"))
+                }
+            }
+        )?,
+        ("status", Some(m)) => run_command_with_result(
+            m,
+            |device| device.xevt_pt(),
+            |result| match result {
+                Some(event) => Ok(event.to_string()),
+                None => Ok("No programming track event".to_string())
+            }
+        )?,
+        ("term", Some(m)) => run_command(m, |device| device.xpt_term())?,
+        _ => ()
+    };
+
+    return Ok(());
+}
+
+pub fn command<'a>() -> App<'a, 'a> {
+    command_group(
+        "pt",
+        "Read and write decoder configuration on the programming track",
+        vec![
+            common_command("read", "Read a configuration variable")
+                .arg(Arg::with_name("cv")
+                    .help("Configuration variable or register number")
+                    .required(true)
+                    .takes_value(true))
+                .arg(mode_arg()),
+            common_command("write", "Write a configuration variable")
+                .arg(Arg::with_name("cv")
+                    .help("Configuration variable or register number")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("value")
+                    .help("Value to write")
+                    .required(true)
+                    .takes_value(true))
+                .arg(mode_arg()),
+            common_command("read-bit", "Read a single bit of a configuration variable")
+                .arg(Arg::with_name("cv")
+                    .help("Configuration variable number")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("bit")
+                    .help("Bit number from 0 to 7")
+                    .required(true)
+                    .takes_value(true)),
+            common_command("write-bit", "Write a single bit of a configuration variable")
+                .arg(Arg::with_name("cv")
+                    .help("Configuration variable number")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("bit")
+                    .help("Bit number from 0 to 7")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("value")
+                    .help("Bit value")
+                    .required(true)
+                    .takes_value(true)),
+            common_command("status", "Get the result of the last programming task"),
+            common_command("term", "Terminate the running programming task")
+        ]
+    )
+}
+
+fn mode_arg<'a>() -> Arg<'a, 'a> {
+    Arg::with_name("mode")
+        .help("Programming mode")
+        .long("mode")
+        .short("m")
+        .takes_value(true)
+        .possible_values(&["direct", "page", "register", "physical", "motorola"])
+        .default_value("direct")
+}
+
+fn parse_mode(value: &str) -> p50x::Result<ProgrammingMode> {
+    match value {
+        "direct" => Ok(ProgrammingMode::Direct),
+        "page" => Ok(ProgrammingMode::Page),
+        "register" => Ok(ProgrammingMode::Register),
+        "physical" => Ok(ProgrammingMode::PhysicalRegister),
+        "motorola" => Ok(ProgrammingMode::Motorola),
+        _ => Err(Error::InvalidArgument(format!("mode '{}'", value)))
+    }
+}
@@ -0,0 +1,122 @@
+/*
+ * File: record.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+use clap::{ArgMatches, App, Arg, SubCommand};
+use p50x::{RecordingTransport, Transport};
+use crate::utils::{common_args, get_unverified_device};
+
+// Interval in which the client and the device are polled for new data
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    let path = matches.value_of("file").unwrap();
+    let address = matches.value_of("listen").unwrap();
+
+    let device = get_unverified_device(matches)?;
+
+    let session = match File::create(path) {
+        Ok(file) => file,
+        Err(err) => return Err(format!("Unable to create {}: {}", path, err))
+    };
+
+    let mut transport = RecordingTransport::new(device.into_transport(), session);
+    if let Err(err) = transport.set_timeout(POLL_INTERVAL) {
+        return Err(err.to_string());
+    }
+
+    let listener = match TcpListener::bind(address) {
+        Ok(listener) => listener,
+        Err(err) => return Err(format!("Unable to listen on {}: {}", address, err))
+    };
+
+    if matches.is_present("quiet") == false {
+        println!("Recording to {}, connect to tcp://{}", path, address);
+    }
+
+    // all connections are recorded into the same session
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => return Err(err.to_string())
+        };
+
+        if let Err(err) = relay(&mut stream, &mut transport) {
+            eprintln!("Connection closed: {}", err);
+        }
+    }
+
+    return Ok(());
+}
+
+fn relay<T: Transport, W: Write>(stream: &mut TcpStream, transport: &mut RecordingTransport<T, W>) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+
+    let mut buffer = [0u8; 256];
+
+    loop {
+        match stream.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(count) => transport.write_all(&buffer[..count])?,
+            Err(err) if is_timeout(&err) => (),
+            Err(err) => return Err(err)
+        }
+
+        match transport.read(&mut buffer) {
+            Ok(count) => stream.write_all(&buffer[..count])?,
+            Err(err) if is_timeout(&err) => (),
+            Err(err) => return Err(err)
+        }
+    }
+}
+
+fn is_timeout(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::TimedOut || err.kind() == io::ErrorKind::WouldBlock
+}
+
+pub fn command<'a>() -> App<'a, 'a> {
+    SubCommand::with_name("record")
+        .about("Record the session of clients connecting via tcp://host:port to a device for later replay")
+        .args(&common_args())
+        .args(&[
+            Arg::with_name("file")
+                .help("File to write the recorded session to")
+                .required(true)
+                .takes_value(true),
+            Arg::with_name("listen")
+                .long("listen")
+                .short("l")
+                .help("Address to listen on")
+                .takes_value(true)
+                .default_value("127.0.0.1:5050")
+        ])
+}
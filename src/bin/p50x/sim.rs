@@ -0,0 +1,90 @@
+/*
+ * File: sim.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::net::TcpListener;
+use clap::{ArgMatches, App, Arg, SubCommand};
+use p50x::ConnectionMode;
+use p50x::sim::Simulator;
+
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    let address = matches.value_of("listen").unwrap();
+
+    let listener = match TcpListener::bind(address) {
+        Ok(listener) => listener,
+        Err(err) => return Err(format!("Unable to listen on {}: {}", address, err))
+    };
+
+    if matches.is_present("quiet") == false {
+        println!("Simulator listening on tcp://{}", address);
+    }
+
+    // connections are served one after another and share the simulated layout state
+    let mut simulator = Simulator::new();
+    simulator.set_mode(match matches.value_of("mode").unwrap() {
+        "ascii" => ConnectionMode::Ascii,
+        "legacy" => ConnectionMode::Legacy,
+        _ => ConnectionMode::Binary
+    });
+
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => return Err(err.to_string())
+        };
+
+        if let Err(err) = simulator.serve(&mut stream) {
+            eprintln!("Connection closed: {}", err);
+        }
+    }
+
+    return Ok(());
+}
+
+pub fn command<'a>() -> App<'a, 'a> {
+    SubCommand::with_name("sim")
+        .about("Run a simulated P50X device to connect to via tcp://host:port")
+        .args(&[
+            Arg::with_name("listen")
+                .long("listen")
+                .short("l")
+                .help("Address to listen on")
+                .takes_value(true)
+                .default_value("127.0.0.1:5050"),
+            Arg::with_name("mode")
+                .long("mode")
+                .short("m")
+                .help("Accepted commands, binary mode accepts P50Xa command lines as well")
+                .takes_value(true)
+                .possible_values(&["binary", "ascii", "legacy"])
+                .default_value("binary"),
+            Arg::with_name("quiet")
+                .long("quiet")
+                .short("q")
+                .help("Prevent output to terminal")
+        ])
+}
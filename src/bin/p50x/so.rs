@@ -27,9 +27,9 @@
  */
 
 use clap::{ArgMatches, App, Arg};
-use p50x::{P50XBinary, Error};
+use p50x::P50XBinary;
 
-use crate::utils::{command_group, common_command, run_command, run_command_with_result};
+use crate::utils::{command_group, common_command, invalid_argument, run_command, run_command_with_result};
 
 pub fn run(matches: &ArgMatches) -> Result<(), String> {
     match matches.subcommand() {
@@ -38,7 +38,7 @@ pub fn run(matches: &ArgMatches) -> Result<(), String> {
             |device| {
                 match m.value_of("special_option").unwrap().parse::<u16>() {
                     Ok(so) => device.xso_get(so),
-                    Err(_) => Err(Error::Other) // TODO: Proper handle parse error
+                    Err(_) => Err(invalid_argument(m, "special_option"))
                 }
             },
             |result| Ok(result.to_string()))?,
@@ -47,12 +47,12 @@ pub fn run(matches: &ArgMatches) -> Result<(), String> {
             |device| {
                 let so = match m.value_of("special_option").unwrap().parse::<u16>() {
                     Ok(so) => so,
-                    Err(_) => return Err(Error::Other) // TODO: Proper handle parse error
+                    Err(_) => return Err(invalid_argument(m, "special_option"))
                 };
 
                 match m.value_of("value").unwrap().parse::<u8>() {
                     Ok(value) => device.xso_set(so, value),
-                    Err(_) => Err(Error::Other) // TODO: Proper handle parse error
+                    Err(_) => Err(invalid_argument(m, "value"))
                 }
             })?,
         _ => ()
@@ -0,0 +1,68 @@
+/*
+ * File: trace.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::time::Instant;
+use log::{Log, Metadata, Record, Level, LevelFilter};
+
+// Prints log messages of the library with the time since the start
+struct TraceLogger {
+    start: Instant
+}
+
+impl Log for TraceLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target().starts_with("p50x")
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) == false {
+            return;
+        }
+
+        let elapsed = self.start.elapsed().as_secs_f64() * 1000.0;
+
+        match record.level() {
+            Level::Trace | Level::Debug => eprintln!("[{:>10.3} ms] {}", elapsed, record.args()),
+            level => eprintln!("[{:>10.3} ms] {}: {}", elapsed, level, record.args())
+        }
+    }
+
+    fn flush(&self) {
+    }
+}
+
+/// Print warnings of the library and all exchanged data if `trace` is set.
+pub fn init(trace: bool) {
+    let logger = TraceLogger {
+        start: Instant::now()
+    };
+
+    if log::set_logger(Box::leak(Box::new(logger))).is_ok() {
+        log::set_max_level(if trace { LevelFilter::Trace } else { LevelFilter::Warn });
+    }
+}
@@ -27,13 +27,13 @@
  */
 
 use clap::{ArgMatches, App, Arg};
-use p50x::{P50XBinary, Error, XTurnoutOptions};
+use p50x::{P50XBinary, XTurnoutOptions};
 
-use crate::utils::{command_group, common_command, run_command, run_command_with_result, str_to_bool};
+use crate::utils::{command_group, common_command, invalid_argument, run_command, run_command_with_outcome, run_command_with_result, str_to_bool};
 
 pub fn run(matches: &ArgMatches) -> Result<(), String> {
     match matches.subcommand() {
-        ("set", Some(m)) => run_command(
+        ("set", Some(m)) => run_command_with_outcome(
             m,
             |device| {
                 let state = str_to_bool(m.value_of("state").unwrap());
@@ -45,7 +45,7 @@ pub fn run(matches: &ArgMatches) -> Result<(), String> {
 
                 match m.value_of("address").unwrap().parse::<u16>() {
                     Ok(address) => device.xturnout(address, state, options),
-                    Err(_) => return Err(Error::Other) // TODO: Proper handle parse error
+                    Err(_) => return Err(invalid_argument(m, "address"))
                 }
             }
         )?,
@@ -55,7 +55,7 @@ pub fn run(matches: &ArgMatches) -> Result<(), String> {
             |device| {
                 match m.value_of("address").unwrap().parse::<u16>() {
                     Ok(address) => device.xturnout_status(address),
-                    Err(_) => return Err(Error::Other) // TODO: Proper handle parse error
+                    Err(_) => return Err(invalid_argument(m, "address"))
                 }
             },
             |result| Ok(result.to_string())
@@ -65,7 +65,7 @@ pub fn run(matches: &ArgMatches) -> Result<(), String> {
             |device| {
                 match m.value_of("group-address").unwrap().parse::<u8>() {
                     Ok(address) => device.xturnout_group(address),
-                    Err(_) => return Err(Error::Other) // TODO: Proper handle parse error
+                    Err(_) => return Err(invalid_argument(m, "group-address"))
                 }
             },
             |data| {
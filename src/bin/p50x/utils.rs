@@ -26,8 +26,11 @@
  * SOFTWARE.
  */
 
+use std::net::TcpStream;
 use clap::{Arg, SubCommand, App, ArgMatches, AppSettings};
-use p50x::{Device, P50XReply, Error};
+use p50x::{Device, P50XReply, Error, Outcome, Transport, open_serial};
+
+pub type AnyDevice = Device<Box<dyn Transport>>;
 
 pub fn command_group<'a>(name: &str, description: &'a str, subcommands: Vec<App<'a, 'a>>) -> App<'a, 'a> {
     SubCommand::with_name(name)
@@ -45,13 +48,13 @@ pub fn common_command<'a>(name: &str, description: &'a str) -> App<'a, 'a> {
 pub fn common_args<'a>() -> Vec<Arg<'a, 'a>> {
     vec![
         Arg::with_name("port")
-            .help("Serial port OS specific name")
+            .help("Serial port OS specific name or tcp://host:port")
             .required(true)
             .takes_value(true),
         Arg::with_name("baud")
             .long("baud")
             .short("b")
-            .help("Serial port baud rate")
+            .help("Serial port baud rate or auto to detect it")
             .takes_value(true)
             .default_value("19200"),
         Arg::with_name("timeout")
@@ -67,11 +70,33 @@ pub fn common_args<'a>() -> Vec<Arg<'a, 'a>> {
     ]
 }
 
-pub fn run_command<F>(matches: &ArgMatches, callback: F) -> Result<(), String> where F: Fn(&mut Device) -> p50x::Result<()> {
+/// Error for an argument value which could not be parsed.
+pub fn invalid_argument(matches: &ArgMatches, name: &str) -> Error {
+    Error::InvalidArgument(format!("{} '{}'", name, matches.value_of(name).unwrap_or_default()))
+}
+
+// Replies are printed with their description only, other errors with all details
+fn error_message(err: &Error) -> String {
+    match err.root() {
+        Error::Reply(reply) => reply.to_string(),
+        _ => err.to_string()
+    }
+}
+
+pub fn run_command<F>(matches: &ArgMatches, callback: F) -> Result<(), String> where F: Fn(&mut AnyDevice) -> p50x::Result<()> {
+    run_command_with_outcome(matches, |device| callback(device).map(Outcome::new))
+}
+
+/// Run a command which may be accepted with warnings, warnings are printed but do not fail the command.
+pub fn run_command_with_outcome<F>(matches: &ArgMatches, callback: F) -> Result<(), String> where F: Fn(&mut AnyDevice) -> p50x::Result<Outcome<()>> {
     let mut device = get_device(matches)?;
 
     match callback(&mut device) {
-        Ok(_) => {
+        Ok(outcome) => {
+            for warning in outcome.warnings {
+                eprintln!("Warning: {}", warning);
+            }
+
             if matches.is_present("quiet") == false {
                 println!("{}", P50XReply::Ok);
             }
@@ -79,10 +104,7 @@ pub fn run_command<F>(matches: &ArgMatches, callback: F) -> Result<(), String> w
             return Ok(());
         },
         Err(err) => {
-            match err {
-                Error::Reply(reply) => Err(reply.to_string()),
-                _ => Err(err.to_string())
-            }
+            Err(error_message(&err))
         }
     }
 }
@@ -91,16 +113,13 @@ pub fn run_command_with_result<F, G, R>(
     matches: &ArgMatches,
     command_callback: F,
     result_callback: G
-) -> Result<(), String> where F: Fn(&mut Device) -> p50x::Result<R>, G: Fn(R) -> Result<String, String> {
+) -> Result<(), String> where F: Fn(&mut AnyDevice) -> p50x::Result<R>, G: Fn(R) -> Result<String, String> {
     let mut device = get_device(matches)?;
 
     let result = match command_callback(&mut device) {
         Ok(result) => result,
         Err(err) => {
-            return match err {
-                Error::Reply(reply) => Err(reply.to_string()),
-                _ => Err(err.to_string())
-            };
+            return Err(error_message(&err));
         }
     };
 
@@ -116,10 +135,37 @@ pub fn run_command_with_result<F, G, R>(
     }
 }
 
-pub fn get_device(matches: &ArgMatches) -> Result<Device, String> {
-    // TODO: Set timeout
+pub fn get_device(matches: &ArgMatches) -> Result<AnyDevice, String> {
+    return open_device(matches, true);
+}
+
+/// Open the device without verifying it understands P50X commands.
+pub fn get_unverified_device(matches: &ArgMatches) -> Result<AnyDevice, String> {
+    return open_device(matches, false);
+}
+
+fn open_device(matches: &ArgMatches, verify: bool) -> Result<AnyDevice, String> {
     let port_name = matches.value_of("port").unwrap();
     let baud_rate_arg = matches.value_of("baud").unwrap();
+    let timeout_arg = matches.value_of("timeout").unwrap();
+
+    let timeout = match timeout_arg.parse::<u64>() {
+        Ok(value) => value,
+        Err(_) => return Err(format!("Invalid timeout: {}", timeout_arg))
+    };
+
+    if baud_rate_arg == "auto" {
+        let mut device = match Device::autodetect_with(|baud_rate| open_transport(port_name, baud_rate)) {
+            Ok((device, _)) => device,
+            Err(err) => return Err(err.to_string())
+        };
+
+        if let Err(err) = device.set_timeout(timeout) {
+            return Err(err.to_string());
+        }
+
+        return Ok(device);
+    }
 
     let baud_rate: u32;
 
@@ -129,10 +175,38 @@ pub fn get_device(matches: &ArgMatches) -> Result<Device, String> {
         return Err(format!("Invalid baud rate: {}", baud_rate_arg));
     }
 
-    match Device::new(port_name, baud_rate) {
-        Ok(device) => Ok(device),
-        Err(err) => Err(err.to_string())
+    let transport = match open_transport(port_name, baud_rate) {
+        Ok(transport) => transport,
+        Err(err) => return Err(err.to_string())
+    };
+
+    let mut device = if verify {
+        match Device::from_transport(transport) {
+            Ok(device) => device,
+            Err(err) => return Err(err.to_string())
+        }
+    } else {
+        Device::from_transport_unverified(transport)
+    };
+
+    if let Err(err) = device.set_timeout(timeout) {
+        return Err(err.to_string());
     }
+
+    return Ok(device);
+}
+
+fn open_transport(port_name: &str, baud_rate: u32) -> p50x::Result<Box<dyn Transport>> {
+    if let Some(address) = port_name.strip_prefix("tcp://") {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+
+        return Ok(Box::new(stream));
+    }
+
+    let serial = open_serial(port_name, baud_rate)?;
+
+    return Ok(Box::new(serial));
 }
 
 pub fn str_to_bool(value: &str) -> bool {
@@ -0,0 +1,497 @@
+/*
+ * File: ascii.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::error::*;
+use super::outcome::{Outcome, WARNING_REPLIES};
+use super::protocol::{XLokOptions, XLokStatus};
+use super::reply::P50XReply;
+
+// Error lines of the textual replies, everything else is treated as regular output
+pub(crate) const ERROR_MESSAGES: [(P50XReply, &str); 11] = [
+    (P50XReply::BadCommand, "Bad cmd"),
+    (P50XReply::BadParameter, "Bad param"),
+    (P50XReply::PowerOff, "Pwr off"),
+    (P50XReply::NoLokCommandSpace, "Lok stack full"),
+    (P50XReply::FullTurnoutCommandStack, "Trn stack full"),
+    (P50XReply::BadLokParameter, "Bad lok addr"),
+    (P50XReply::LokBusy, "Lok busy"),
+    (P50XReply::BadTurnoutParameter, "Bad trn addr"),
+    (P50XReply::BadSpecialOptionValue, "Bad SO value"),
+    (P50XReply::LokHalt, "Halted!"),
+    (P50XReply::LokPowerOff, "Pwr Off!")
+];
+
+// Replies without an own message are reported with their code
+pub(crate) fn error_message(reply: P50XReply) -> String {
+    for (error, message) in ERROR_MESSAGES.iter() {
+        if *error == reply {
+            return message.to_string();
+        }
+    }
+
+    return format!("Error {}", u8::from(reply));
+}
+
+/// Reply to a P50Xa command.
+///
+/// The device answers with zero or more lines followed by the `]` prompt. An error is reported as a single line
+/// which is converted into its P50Xb reply code. `command` is the line which was sent, it is empty for parsed
+/// replies.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AsciiReply {
+    pub command: String,
+    pub status: P50XReply,
+    pub lines: Vec<String>
+}
+
+impl AsciiReply {
+    pub fn parse(text: &str) -> AsciiReply {
+        let lines: Vec<String> = text
+            .split(['\r', '\n'])
+            .map(|line| line.trim())
+            .filter(|line| line.is_empty() == false)
+            .map(|line| line.to_string())
+            .collect();
+
+        if lines.len() == 1 {
+            for (error, message) in ERROR_MESSAGES.iter() {
+                if lines[0] == *message {
+                    return AsciiReply {
+                        command: String::new(),
+                        status: *error,
+                        lines: Vec::new()
+                    };
+                }
+            }
+
+            if let Some(Ok(code)) = lines[0].strip_prefix("Error ").map(|code| code.trim().parse::<u8>()) {
+                return AsciiReply {
+                    command: String::new(),
+                    status: P50XReply::from(code),
+                    lines: Vec::new()
+                };
+            }
+        }
+
+        return AsciiReply {
+            command: String::new(),
+            status: P50XReply::Ok,
+            lines
+        };
+    }
+
+    pub fn into_lines(self) -> Result<Vec<String>> {
+        if self.status != P50XReply::Ok {
+            return Err(self.error());
+        }
+
+        return Ok(self.lines);
+    }
+
+    /// Lines of the reply, warnings are returned with the outcome instead of as an error.
+    pub fn into_outcome(self) -> Result<Outcome<Vec<String>>> {
+        if WARNING_REPLIES.contains(&self.status) {
+            return Ok(Outcome::from_reply(self.lines, self.status));
+        }
+
+        return self.into_lines().map(Outcome::new);
+    }
+
+    fn error(&self) -> Error {
+        if self.command.is_empty() {
+            return Error::from(self.status);
+        }
+
+        return Error::ascii_command(&self.command, Error::from(self.status));
+    }
+
+    // Values of a single line reply without the echoed command and address
+    fn values(self, skip: usize) -> Result<Vec<String>> {
+        let lines = self.into_lines()?;
+
+        if lines.len() != 1 {
+            return Err(Error::UnknownResponse(lines.join("\n")));
+        }
+
+        return Ok(fields(&lines[0]).into_iter().skip(skip).map(|field| field.to_string()).collect());
+    }
+}
+
+impl fmt::Display for AsciiReply {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.status != P50XReply::Ok {
+            return write!(f, "{}", self.status);
+        }
+
+        write!(f, "{}", self.lines.join("\n"))
+    }
+}
+
+pub(crate) fn fields(line: &str) -> Vec<&str> {
+    line
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|field| field.is_empty() == false)
+        .collect()
+}
+
+fn parse_value<T: FromStr>(value: &str) -> Result<T> {
+    match value.parse::<T>() {
+        Ok(value) => Ok(value),
+        Err(_) => Err(Error::UnknownResponse(value.to_string()))
+    }
+}
+
+fn parse_flags(values: &[String]) -> Result<Vec<bool>> {
+    let mut result = Vec::new();
+
+    for value in values {
+        match value.as_str() {
+            "0" => result.push(false),
+            "1" => result.push(true),
+            _ => return Err(Error::UnknownResponse(value.to_string()))
+        }
+    }
+
+    return Ok(result);
+}
+
+fn flag(value: bool) -> &'static str {
+    if value {
+        "1"
+    } else {
+        "0"
+    }
+}
+
+fn flags(values: &[bool]) -> String {
+    values.iter().map(|value| flag(*value)).collect::<Vec<&str>>().join(", ")
+}
+
+/// Textual P50Xa commands.
+///
+/// Only `acommand` has to be implemented, all other commands are built on top of it and mirror the corresponding
+/// `P50XBinary` commands.
+pub trait P50XAscii {
+    fn acommand(&mut self, command: &str) -> Result<AsciiReply>;
+
+    fn apower_on(&mut self) -> Result<()> {
+        self.acommand("Go")?.into_lines()?;
+
+        return Ok(());
+    }
+
+    fn apower_off(&mut self) -> Result<()> {
+        self.acommand("Stop")?.into_lines()?;
+
+        return Ok(());
+    }
+
+    fn ahalt(&mut self) -> Result<()> {
+        self.acommand("Halt")?.into_lines()?;
+
+        return Ok(());
+    }
+
+    fn ahelp(&mut self) -> Result<Vec<String>> {
+        self.acommand("?")?.into_lines()
+    }
+
+    fn aversion(&mut self) -> Result<Vec<String>> {
+        self.acommand("V")?.into_lines()
+    }
+
+    fn aso_set(&mut self, special_option: u16, value: u8) -> Result<()> {
+        self.acommand(&format!("SO {}, {}", special_option, value))?.into_lines()?;
+
+        return Ok(());
+    }
+
+    fn aso_get(&mut self, special_option: u16) -> Result<u8> {
+        let values = self.acommand(&format!("SO {}", special_option))?.values(2)?;
+
+        match values.as_slice() {
+            [value] => parse_value(value),
+            _ => Err(Error::UnknownResponse(values.join(" ")))
+        }
+    }
+
+    fn asensor(&mut self, module: u8) -> Result<[bool; 16]> {
+        let values = self.acommand(&format!("S {}", module))?.values(2)?;
+
+        if values.len() != 1 || values[0].len() != 16 {
+            return Err(Error::UnknownResponse(values.join(" ")));
+        }
+
+        let contacts: Vec<String> = values[0].chars().map(|c| c.to_string()).collect();
+        let mut result = [false; 16];
+        result.copy_from_slice(&parse_flags(&contacts)?);
+
+        return Ok(result);
+    }
+
+    /// Set the locomotive speed. There is no force flag in P50Xa so `options.force` is ignored.
+    fn alok(&mut self, address: u16, speed: i8, options: XLokOptions) -> Result<Outcome<()>> {
+        // speed 1 maps to emergency stop
+        let speed_value = if options.emergency_stop {
+            1
+        } else {
+            speed.unsigned_abs()
+        };
+
+        let direction = if speed < 0 || options.reverse {
+            "r"
+        } else {
+            "f"
+        };
+
+        let mut command = format!("L {}, {}, {}, {}", address, speed_value, flag(options.light), direction);
+
+        if let Some(functions) = options.functions {
+            command = format!("{}, {}", command, flags(&functions));
+        }
+
+        return self.acommand(&command)?.into_outcome().map(|outcome| outcome.map(|_| ()));
+    }
+
+    fn alok_status(&mut self, address: u16) -> Result<XLokStatus> {
+        let values = self.acommand(&format!("L {}", address))?.values(2)?;
+
+        if values.len() != 7 {
+            return Err(Error::UnknownResponse(values.join(" ")));
+        }
+
+        let speed: i8 = parse_value(&values[0])?;
+        let light = parse_flags(&values[1..2])?[0];
+        let functions = parse_flags(&values[3..7])?;
+
+        let reverse = match values[2].as_str() {
+            "f" => false,
+            "r" => true,
+            _ => return Err(Error::UnknownResponse(values[2].to_string()))
+        };
+
+        let speed = if reverse {
+            -speed
+        } else {
+            speed
+        };
+
+        return Ok(XLokStatus {
+            speed,
+            real_speed: speed,
+            options: XLokOptions {
+                emergency_stop: false,
+                force: false,
+                light,
+                reverse,
+                functions: Some([functions[0], functions[1], functions[2], functions[3]])
+            }
+        });
+    }
+
+    fn afunc(&mut self, address: u16, functions: [bool; 8]) -> Result<Outcome<()>> {
+        return self.acommand(&format!("F {}, {}", address, flags(&functions)))?.into_outcome().map(|outcome| outcome.map(|_| ()));
+    }
+
+    fn afunc_status(&mut self, address: u16) -> Result<[bool; 8]> {
+        let values = self.acommand(&format!("F {}", address))?.values(2)?;
+
+        if values.len() != 8 {
+            return Err(Error::UnknownResponse(values.join(" ")));
+        }
+
+        let mut result = [false; 8];
+        result.copy_from_slice(&parse_flags(&values)?);
+
+        return Ok(result);
+    }
+
+    fn afuncx(&mut self, address: u16, functions: [bool; 8]) -> Result<Outcome<()>> {
+        return self.acommand(&format!("FX {}, {}", address, flags(&functions)))?.into_outcome().map(|outcome| outcome.map(|_| ()));
+    }
+
+    fn afuncx_status(&mut self, address: u16) -> Result<[bool; 8]> {
+        let values = self.acommand(&format!("FX {}", address))?.values(2)?;
+
+        if values.len() != 8 {
+            return Err(Error::UnknownResponse(values.join(" ")));
+        }
+
+        let mut result = [false; 8];
+        result.copy_from_slice(&parse_flags(&values)?);
+
+        return Ok(result);
+    }
+
+    /// Switch a turnout to green (`true`) or red (`false`) and turn the output on or off.
+    fn aturnout(&mut self, address: u16, state: bool, on: bool) -> Result<Outcome<()>> {
+        let color = if state {
+            "g"
+        } else {
+            "r"
+        };
+
+        return self.acommand(&format!("T {}, {}, {}", address, color, flag(on)))?.into_outcome().map(|outcome| outcome.map(|_| ()));
+    }
+
+    fn aturnout_status(&mut self, address: u16) -> Result<bool> {
+        let values = self.acommand(&format!("T {}", address))?.values(2)?;
+
+        match values.as_slice() {
+            [color] if color == "g" => Ok(true),
+            [color] if color == "r" => Ok(false),
+            _ => Err(Error::UnknownResponse(values.join(" ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Device, P50XBinary};
+    use crate::protocol::ConnectionMode;
+    use crate::sim::Simulator;
+
+    #[test]
+    fn reply_lines_are_parsed() {
+        let reply = AsciiReply::parse("L 3 50 1 f 0 0 0 0\r");
+
+        assert_eq!(reply.status, P50XReply::Ok);
+        assert_eq!(reply.lines, vec!["L 3 50 1 f 0 0 0 0"]);
+        assert_eq!(fields(&reply.lines[0]).len(), 9);
+
+        let reply = AsciiReply::parse("\r\n");
+        assert_eq!(reply.status, P50XReply::Ok);
+        assert!(reply.lines.is_empty());
+    }
+
+    #[test]
+    fn reply_errors_are_converted() {
+        assert_eq!(AsciiReply::parse("Bad lok addr\r").status, P50XReply::BadLokParameter);
+        assert_eq!(AsciiReply::parse("Halted!\r").status, P50XReply::LokHalt);
+        assert_eq!(AsciiReply::parse("Error 42\r").status, P50XReply::Unknown(42));
+        assert_eq!(AsciiReply::parse("Error 128\r").status, P50XReply::Busy);
+
+        match AsciiReply::parse("Bad param\r").into_lines() {
+            Err(Error::Reply(P50XReply::BadParameter)) => (),
+            result => panic!("Unexpected result {:?}", result)
+        }
+    }
+
+    fn ascii_device() -> Device<Simulator> {
+        let mut simulator = Simulator::new();
+        simulator.set_mode(ConnectionMode::Ascii);
+
+        return Device::from_transport_unverified(simulator);
+    }
+
+    #[test]
+    fn ascii_commands_share_state_with_binary_commands() {
+        let mut device = ascii_device();
+        device.apower_on().unwrap();
+
+        let options = XLokOptions {
+            light: true,
+            functions: Some([true, true, false, true]),
+            ..Default::default()
+        };
+        device.alok(3, -20, options).unwrap();
+        device.afuncx(3, [false, true, false, false, false, false, false, false]).unwrap();
+        device.aturnout(12, true, true).unwrap();
+        device.aso_set(7, 42).unwrap();
+
+        let status = device.alok_status(3).unwrap();
+        assert_eq!(status.speed, -20);
+        assert!(status.options.light);
+        assert_eq!(status.options.functions, Some([true, true, false, true]));
+        assert!(device.aturnout_status(12).unwrap());
+
+        match device.alok(0, 10, XLokOptions::default()) {
+            Err(ref err) if err.reply() == Some(P50XReply::BadLokParameter) => (),
+            result => panic!("Unexpected result {:?}", result)
+        }
+
+        assert_eq!(device.acommand("Z").unwrap().status, P50XReply::BadCommand);
+
+        device.acommand("ZzA1").unwrap().into_lines().unwrap();
+
+        let status = device.xlok_status(3).unwrap();
+        assert_eq!(status.speed, -20);
+        assert_eq!(status.options.functions, Some([true, true, false, true]));
+        assert_eq!(device.xfuncx_status(3).unwrap(), [false, true, false, false, false, false, false, false]);
+        assert!(device.xturnout_status(12).unwrap().state);
+        assert!(device.xstatus().unwrap().power);
+        assert_eq!(device.xso_get(7).unwrap(), 42);
+    }
+
+    #[test]
+    fn ascii_warnings_are_returned_with_the_outcome() {
+        let mut device = ascii_device();
+
+        // the command is still accepted
+        let outcome = device.alok(3, 10, XLokOptions::default()).unwrap();
+        assert_eq!(outcome.warnings, vec![P50XReply::LokPowerOff]);
+        assert_eq!(device.alok_status(3).unwrap().speed, 10);
+
+        device.apower_on().unwrap();
+        assert!(device.afunc(3, [true; 8]).unwrap().has_warnings() == false);
+    }
+
+    #[test]
+    fn ascii_errors_report_command() {
+        let mut device = ascii_device();
+
+        match device.alok(0, 10, XLokOptions::default()) {
+            Err(ref err @ Error::AsciiCommand { .. }) => {
+                assert_eq!(err.reply(), Some(P50XReply::BadLokParameter));
+                assert!(err.to_string().ends_with("(command 'L 0, 10, 0, f')"));
+            },
+            result => panic!("Unexpected result {:?}", result)
+        }
+    }
+
+    #[test]
+    fn ascii_lines_are_not_accepted_in_binary_mode() {
+        let mut simulator = Simulator::new();
+        simulator.set_power(true);
+
+        let mut device = Device::from_transport(simulator).unwrap();
+        device.set_timeout(50).unwrap();
+
+        // the bytes are read as original P50 commands which have no reply
+        match device.acommand("V") {
+            Err(ref err) if err.is_timeout() => (),
+            result => panic!("Unexpected result {:?}", result)
+        }
+
+        assert_eq!(device.into_transport().mode(), ConnectionMode::Binary);
+    }
+}
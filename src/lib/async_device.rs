@@ -0,0 +1,649 @@
+/*
+ * File: async_device.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::future::Future;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time;
+use log::warn;
+
+use super::device::{Device, PROBE_TIMEOUT, RESYNCHRONIZE_ATTEMPTS};
+use super::error::*;
+use super::opcode;
+use super::outcome::Outcome;
+use super::protocol::*;
+use super::trace;
+use super::transport::Transport;
+use super::version::VersionInfo;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Asynchronous version of `P50XBinary` with the same commands and replies.
+///
+/// The returned futures are `Send`, so commands can be run in spawned tasks.
+pub trait AsyncP50XBinary {
+    fn xpower_off(&mut self) -> impl Future<Output = Result<()>> + Send;
+    fn xpower_on(&mut self) -> impl Future<Output = Result<()>> + Send;
+    fn xhalt(&mut self) -> impl Future<Output = Result<()>> + Send;
+    fn xso_set(&mut self, special_option: u16, value: u8) -> impl Future<Output = Result<()>> + Send;
+    fn xso_get(&mut self, special_option: u16) -> impl Future<Output = Result<u8>> + Send;
+    fn xversion(&mut self) -> impl Future<Output = Result<VersionInfo>> + Send;
+    fn xp50xch(&mut self, extended_character: u8) -> impl Future<Output = Result<()>> + Send;
+    fn xstatus(&mut self) -> impl Future<Output = Result<DeviceStatus>> + Send;
+    fn xnop(&mut self) -> impl Future<Output = Result<()>> + Send;
+
+    fn xsensor(&mut self, module: u8) -> impl Future<Output = Result<[bool; 16]>> + Send;
+    fn xsens_off(&mut self) -> impl Future<Output = Result<()>> + Send;
+    fn x88p_get(&mut self, parameter: u8) -> impl Future<Output = Result<u8>> + Send;
+    fn x88p_set(&mut self, parameter: u8, value: u8) -> impl Future<Output = Result<()>> + Send;
+    fn xs88_timer(&mut self, timer: u8, reset: bool) -> impl Future<Output = Result<u16>> + Send;
+    fn xs88_count(&mut self, timer: u8, reset: bool) -> impl Future<Output = Result<u16>> + Send;
+
+    fn xlok(&mut self, address: u16, speed: i8, options: XLokOptions) -> impl Future<Output = Result<Outcome<()>>> + Send;
+    fn xlok_status(&mut self, address: u16) -> impl Future<Output = Result<XLokStatus>> + Send;
+    fn xlok_config(&mut self, address: u16) -> impl Future<Output = Result<XLokConfig>> + Send;
+    fn xlok_set_config(&mut self, address: u16, config: XLokConfig) -> impl Future<Output = Result<()>> + Send;
+    fn xlok_dispatch(&mut self, address: u16) -> impl Future<Output = Result<Option<u8>>> + Send;
+    fn xfunc(&mut self, address: u16, functions: [bool; 8]) -> impl Future<Output = Result<Outcome<()>>> + Send;
+    fn xfunc_status(&mut self, address: u16) -> impl Future<Output = Result<[bool; 8]>> + Send;
+    fn xfuncx(&mut self, address: u16, functions: [bool; 8]) -> impl Future<Output = Result<Outcome<()>>> + Send;
+    fn xfuncx_status(&mut self, address: u16) -> impl Future<Output = Result<[bool; 8]>> + Send;
+    fn xfunc34(&mut self, address: u16, functions: [bool; 16]) -> impl Future<Output = Result<Outcome<()>>> + Send;
+    fn xfunc34_status(&mut self, address: u16) -> impl Future<Output = Result<[bool; 16]>> + Send;
+
+    fn xturnout(&mut self, address: u16, state: bool, options: XTurnoutOptions) -> impl Future<Output = Result<Outcome<()>>> + Send;
+    fn xturnout_free(&mut self) -> impl Future<Output = Result<()>> + Send;
+    fn xturnout_status(&mut self, address: u16) -> impl Future<Output = Result<XTurnoutStatus>> + Send;
+    fn xturnout_group(&mut self, group_address: u8) -> impl Future<Output = Result<[(bool, bool); 8]>> + Send;
+
+    fn xevent(&mut self) -> impl Future<Output = Result<XEventStatus>> + Send;
+    fn xevt_lok(&mut self) -> impl Future<Output = Result<Vec<XLokEvent>>> + Send;
+    fn xevt_turnout(&mut self) -> impl Future<Output = Result<Vec<XTurnoutEvent>>> + Send;
+    fn xevt_sensor(&mut self) -> impl Future<Output = Result<Vec<XSensorEvent>>> + Send;
+
+    fn xevt_pt(&mut self) -> impl Future<Output = Result<Option<XPTEvent>>> + Send;
+    fn xpt_term(&mut self) -> impl Future<Output = Result<()>> + Send;
+    fn xpt_motorola_write_register(&mut self, register: u8, value: u8) -> impl Future<Output = Result<()>> + Send;
+    fn xpt_dcc_read_register(&mut self, register: u8) -> impl Future<Output = Result<()>> + Send;
+    fn xpt_dcc_write_register(&mut self, register: u8, value: u8) -> impl Future<Output = Result<()>> + Send;
+    fn xpt_dcc_read_page(&mut self, cv: u16) -> impl Future<Output = Result<()>> + Send;
+    fn xpt_dcc_write_page(&mut self, cv: u16, value: u8) -> impl Future<Output = Result<()>> + Send;
+    fn xpt_dcc_read_physical_register(&mut self, register: u8) -> impl Future<Output = Result<()>> + Send;
+    fn xpt_dcc_write_physical_register(&mut self, register: u8, value: u8) -> impl Future<Output = Result<()>> + Send;
+    fn xpt_dcc_read_byte(&mut self, cv: u16) -> impl Future<Output = Result<()>> + Send;
+    fn xpt_dcc_read_bit(&mut self, cv: u16, bit: u8) -> impl Future<Output = Result<()>> + Send;
+    fn xpt_dcc_write_bit(&mut self, cv: u16, bit: u8, value: bool) -> impl Future<Output = Result<()>> + Send;
+    fn xpt_dcc_write_byte(&mut self, cv: u16, value: u8) -> impl Future<Output = Result<()>> + Send;
+
+    fn xpom_read(&mut self, address: u16, cv: u16) -> impl Future<Output = Result<u8>> + Send;
+    fn xpom_write(&mut self, address: u16, cv: u16, value: u8) -> impl Future<Output = Result<()>> + Send;
+    fn xpom_write_bit(&mut self, address: u16, cv: u16, bit: u8, value: bool) -> impl Future<Output = Result<()>> + Send;
+}
+
+// In-memory transport the commands of a synchronous device are encoded into and decoded from. Reads are handed out
+// a single byte at a time so the device never buffers more than it consumes.
+#[derive(Default)]
+struct CommandBuffer {
+    input: Vec<u8>,
+    position: usize,
+    output: Vec<u8>
+}
+
+impl Read for CommandBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.input.len() || buf.is_empty() {
+            return Err(io::Error::from(io::ErrorKind::WouldBlock));
+        }
+
+        buf[0] = self.input[self.position];
+        self.position += 1;
+
+        return Ok(1);
+    }
+}
+
+impl Write for CommandBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.extend_from_slice(buf);
+
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return Ok(());
+    }
+}
+
+impl Transport for CommandBuffer {}
+
+/// P50X device over an asynchronous byte stream, e.g. a `tokio::net::TcpStream`.
+///
+/// Commands are encoded and replies decoded exactly like `Device` does. Dropping a command before it is done is safe,
+/// its reply is dropped and the reply stream is resynchronized before the next command.
+pub struct AsyncDevice<T: AsyncRead + AsyncWrite + Unpin> {
+    stream: T,
+    device: Device<CommandBuffer>,
+    timeout: Duration,
+    // a command was sent and its reply is not read completely
+    pending: bool
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> AsyncDevice<T> {
+    pub async fn from_stream(stream: T) -> Result<AsyncDevice<T>> {
+        let mut device = AsyncDevice::from_stream_unverified(stream);
+
+        // verify device is p50x device
+        let verified = device.run(|device| device.verify_connection()).await?;
+        if verified == false {
+            return Err(Error::UnknownDevice);
+        }
+
+        return Ok(device);
+    }
+
+    pub fn from_stream_unverified(stream: T) -> AsyncDevice<T> {
+        let mut device = Device::from_transport_unverified(CommandBuffer::default());
+        device.set_auto_resynchronize(false);
+        device.set_traced(false);
+
+        AsyncDevice {
+            stream,
+            device,
+            timeout: DEFAULT_TIMEOUT,
+            pending: false
+        }
+    }
+
+    pub fn set_timeout(&mut self, timeout: u64) {
+        self.timeout = Duration::from_millis(timeout);
+    }
+
+    pub fn into_stream(self) -> T {
+        self.stream
+    }
+
+    // Run a synchronous command against the buffered reply. Whenever it runs out of reply bytes more are read from
+    // the stream and the command is decoded again from the start, only newly written bytes are sent.
+    async fn run<R, F>(&mut self, mut command: F) -> Result<R> where F: FnMut(&mut Device<CommandBuffer>) -> Result<R> {
+        // a dropped command may have left its reply or an incomplete frame behind
+        if self.pending || self.device.is_synchronized() == false {
+            self.resynchronize().await?;
+        }
+
+        self.pending = true;
+
+        let buffer = self.device.transport_mut();
+        buffer.input.clear();
+
+        let extended_character = self.device.extended_character();
+        let mut sent = 0;
+
+        loop {
+            // every pass starts from the same state, only the received reply grows
+            self.device.reset_read_buffer();
+            self.device.set_synchronized(true);
+
+            let buffer = self.device.transport_mut();
+            buffer.position = 0;
+            buffer.output.clear();
+
+            let result = command(&mut self.device);
+
+            let buffer = self.device.transport_mut();
+            if buffer.output.len() > sent {
+                trace::outgoing(&buffer.output[sent..], extended_character);
+                self.stream.write_all(&buffer.output[sent..]).await?;
+                self.stream.flush().await?;
+
+                sent = buffer.output.len();
+            }
+
+            match result {
+                // only the buffer runs out of data, so the reply is incomplete
+                Err(ref err) if err.is_timeout() => {
+                    let mut data = [0; 256];
+                    let count = match time::timeout(self.timeout, self.stream.read(&mut data)).await {
+                        Ok(count) => count?,
+                        // the device stays unsynchronized as the reply might still arrive
+                        Err(_) => {
+                            self.pending = false;
+
+                            return result;
+                        }
+                    };
+
+                    if count == 0 {
+                        return Err(Error::from(io::Error::from(io::ErrorKind::UnexpectedEof)));
+                    }
+
+                    trace::incoming(&data[..count]);
+                    buffer.input.extend_from_slice(&data[..count]);
+                },
+                result => {
+                    // bytes after the reply mean it was longer than expected
+                    if buffer.position < buffer.input.len() {
+                        self.device.set_synchronized(false);
+                    }
+
+                    self.pending = false;
+
+                    return result;
+                }
+            }
+        }
+    }
+
+    // Drop all pending input and check with XNOP that replies match their commands again
+    async fn resynchronize(&mut self) -> Result<()> {
+        let nop = [self.device.extended_character(), opcode::XNOP];
+        let mut discarded = Vec::new();
+
+        for _ in 0..RESYNCHRONIZE_ATTEMPTS {
+            discarded.append(&mut self.probe(&[]).await?);
+
+            let mut reply = self.probe(&nop).await?;
+            if reply == [0x00] {
+                warn!("Reply stream was resynchronized, dropped {:02X?}", discarded);
+
+                self.pending = false;
+                self.device.set_synchronized(true);
+
+                return Ok(());
+            }
+
+            discarded.append(&mut reply);
+        }
+
+        warn!("Reply stream could not be resynchronized, dropped {:02X?}", discarded);
+
+        return Err(Error::Desynchronized);
+    }
+
+    // Send data and collect everything received until the device stays silent
+    async fn probe(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        trace::outgoing(data, self.device.extended_character());
+        self.stream.write_all(data).await?;
+        self.stream.flush().await?;
+
+        let mut reply = Vec::new();
+
+        loop {
+            let mut data = [0; 256];
+
+            match time::timeout(PROBE_TIMEOUT, self.stream.read(&mut data)).await {
+                Ok(Ok(0)) => return Err(Error::from(io::Error::from(io::ErrorKind::UnexpectedEof))),
+                Ok(Ok(count)) => {
+                    trace::incoming(&data[..count]);
+                    reply.extend_from_slice(&data[..count]);
+                },
+                Ok(Err(err)) => return Err(Error::from(err)),
+                Err(_) => break
+            }
+        }
+
+        return Ok(reply);
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> AsyncP50XBinary for AsyncDevice<T> {
+    fn xpower_off(&mut self) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpower_off())
+    }
+
+    fn xpower_on(&mut self) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpower_on())
+    }
+
+    fn xhalt(&mut self) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xhalt())
+    }
+
+    fn xso_set(&mut self, special_option: u16, value: u8) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xso_set(special_option, value))
+    }
+
+    fn xso_get(&mut self, special_option: u16) -> impl Future<Output = Result<u8>> + Send {
+        self.run(move |device| device.xso_get(special_option))
+    }
+
+    fn xversion(&mut self) -> impl Future<Output = Result<VersionInfo>> + Send {
+        self.run(move |device| device.xversion())
+    }
+
+    fn xp50xch(&mut self, extended_character: u8) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xp50xch(extended_character))
+    }
+
+    fn xstatus(&mut self) -> impl Future<Output = Result<DeviceStatus>> + Send {
+        self.run(move |device| device.xstatus())
+    }
+
+    fn xnop(&mut self) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xnop())
+    }
+
+    fn xsensor(&mut self, module: u8) -> impl Future<Output = Result<[bool; 16]>> + Send {
+        self.run(move |device| device.xsensor(module))
+    }
+
+    fn xsens_off(&mut self) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xsens_off())
+    }
+
+    fn x88p_get(&mut self, parameter: u8) -> impl Future<Output = Result<u8>> + Send {
+        self.run(move |device| device.x88p_get(parameter))
+    }
+
+    fn x88p_set(&mut self, parameter: u8, value: u8) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.x88p_set(parameter, value))
+    }
+
+    fn xs88_timer(&mut self, timer: u8, reset: bool) -> impl Future<Output = Result<u16>> + Send {
+        self.run(move |device| device.xs88_timer(timer, reset))
+    }
+
+    fn xs88_count(&mut self, timer: u8, reset: bool) -> impl Future<Output = Result<u16>> + Send {
+        self.run(move |device| device.xs88_count(timer, reset))
+    }
+
+    fn xlok(&mut self, address: u16, speed: i8, options: XLokOptions) -> impl Future<Output = Result<Outcome<()>>> + Send {
+        self.run(move |device| device.xlok(address, speed, options))
+    }
+
+    fn xlok_status(&mut self, address: u16) -> impl Future<Output = Result<XLokStatus>> + Send {
+        self.run(move |device| device.xlok_status(address))
+    }
+
+    fn xlok_config(&mut self, address: u16) -> impl Future<Output = Result<XLokConfig>> + Send {
+        self.run(move |device| device.xlok_config(address))
+    }
+
+    fn xlok_set_config(&mut self, address: u16, config: XLokConfig) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xlok_set_config(address, config))
+    }
+
+    fn xlok_dispatch(&mut self, address: u16) -> impl Future<Output = Result<Option<u8>>> + Send {
+        self.run(move |device| device.xlok_dispatch(address))
+    }
+
+    fn xfunc(&mut self, address: u16, functions: [bool; 8]) -> impl Future<Output = Result<Outcome<()>>> + Send {
+        self.run(move |device| device.xfunc(address, functions))
+    }
+
+    fn xfunc_status(&mut self, address: u16) -> impl Future<Output = Result<[bool; 8]>> + Send {
+        self.run(move |device| device.xfunc_status(address))
+    }
+
+    fn xfuncx(&mut self, address: u16, functions: [bool; 8]) -> impl Future<Output = Result<Outcome<()>>> + Send {
+        self.run(move |device| device.xfuncx(address, functions))
+    }
+
+    fn xfuncx_status(&mut self, address: u16) -> impl Future<Output = Result<[bool; 8]>> + Send {
+        self.run(move |device| device.xfuncx_status(address))
+    }
+
+    fn xfunc34(&mut self, address: u16, functions: [bool; 16]) -> impl Future<Output = Result<Outcome<()>>> + Send {
+        self.run(move |device| device.xfunc34(address, functions))
+    }
+
+    fn xfunc34_status(&mut self, address: u16) -> impl Future<Output = Result<[bool; 16]>> + Send {
+        self.run(move |device| device.xfunc34_status(address))
+    }
+
+    fn xturnout(&mut self, address: u16, state: bool, options: XTurnoutOptions) -> impl Future<Output = Result<Outcome<()>>> + Send {
+        self.run(move |device| device.xturnout(address, state, options))
+    }
+
+    fn xturnout_free(&mut self) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xturnout_free())
+    }
+
+    fn xturnout_status(&mut self, address: u16) -> impl Future<Output = Result<XTurnoutStatus>> + Send {
+        self.run(move |device| device.xturnout_status(address))
+    }
+
+    fn xturnout_group(&mut self, group_address: u8) -> impl Future<Output = Result<[(bool, bool); 8]>> + Send {
+        self.run(move |device| device.xturnout_group(group_address))
+    }
+
+    fn xevent(&mut self) -> impl Future<Output = Result<XEventStatus>> + Send {
+        self.run(move |device| device.xevent())
+    }
+
+    fn xevt_lok(&mut self) -> impl Future<Output = Result<Vec<XLokEvent>>> + Send {
+        self.run(move |device| device.xevt_lok())
+    }
+
+    fn xevt_turnout(&mut self) -> impl Future<Output = Result<Vec<XTurnoutEvent>>> + Send {
+        self.run(move |device| device.xevt_turnout())
+    }
+
+    fn xevt_sensor(&mut self) -> impl Future<Output = Result<Vec<XSensorEvent>>> + Send {
+        self.run(move |device| device.xevt_sensor())
+    }
+
+    fn xevt_pt(&mut self) -> impl Future<Output = Result<Option<XPTEvent>>> + Send {
+        self.run(move |device| device.xevt_pt())
+    }
+
+    fn xpt_term(&mut self) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpt_term())
+    }
+
+    fn xpt_motorola_write_register(&mut self, register: u8, value: u8) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpt_motorola_write_register(register, value))
+    }
+
+    fn xpt_dcc_read_register(&mut self, register: u8) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpt_dcc_read_register(register))
+    }
+
+    fn xpt_dcc_write_register(&mut self, register: u8, value: u8) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpt_dcc_write_register(register, value))
+    }
+
+    fn xpt_dcc_read_page(&mut self, cv: u16) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpt_dcc_read_page(cv))
+    }
+
+    fn xpt_dcc_write_page(&mut self, cv: u16, value: u8) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpt_dcc_write_page(cv, value))
+    }
+
+    fn xpt_dcc_read_physical_register(&mut self, register: u8) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpt_dcc_read_physical_register(register))
+    }
+
+    fn xpt_dcc_write_physical_register(&mut self, register: u8, value: u8) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpt_dcc_write_physical_register(register, value))
+    }
+
+    fn xpt_dcc_read_byte(&mut self, cv: u16) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpt_dcc_read_byte(cv))
+    }
+
+    fn xpt_dcc_read_bit(&mut self, cv: u16, bit: u8) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpt_dcc_read_bit(cv, bit))
+    }
+
+    fn xpt_dcc_write_bit(&mut self, cv: u16, bit: u8, value: bool) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpt_dcc_write_bit(cv, bit, value))
+    }
+
+    fn xpt_dcc_write_byte(&mut self, cv: u16, value: u8) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpt_dcc_write_byte(cv, value))
+    }
+
+    fn xpom_read(&mut self, address: u16, cv: u16) -> impl Future<Output = Result<u8>> + Send {
+        self.run(move |device| device.xpom_read(address, cv))
+    }
+
+    fn xpom_write(&mut self, address: u16, cv: u16, value: u8) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpom_write(address, cv, value))
+    }
+
+    fn xpom_write_bit(&mut self, address: u16, cv: u16, bit: u8, value: bool) -> impl Future<Output = Result<()>> + Send {
+        self.run(move |device| device.xpom_write_bit(address, cv, bit, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::{Context, Poll};
+    use tokio::io::ReadBuf;
+
+    use super::*;
+    use crate::legacy::P50Legacy;
+    use crate::reply::P50XReply;
+    use crate::sim::Simulator;
+
+    // Simulator as asynchronous stream, reads stay pending while it has nothing to send or replies are held back
+    struct AsyncSimulator {
+        simulator: Simulator,
+        held: Arc<AtomicBool>
+    }
+
+    impl AsyncSimulator {
+        fn new(simulator: Simulator) -> AsyncSimulator {
+            AsyncSimulator {
+                simulator,
+                held: Arc::new(AtomicBool::new(false))
+            }
+        }
+    }
+
+    impl AsyncRead for AsyncSimulator {
+        fn poll_read(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+            if self.held.load(Ordering::SeqCst) {
+                return Poll::Pending;
+            }
+
+            match self.simulator.read(buf.initialize_unfilled()) {
+                Ok(count) => {
+                    buf.advance(count);
+
+                    return Poll::Ready(Ok(()));
+                },
+                Err(ref err) if err.kind() == io::ErrorKind::TimedOut => return Poll::Pending,
+                Err(err) => return Poll::Ready(Err(err))
+            }
+        }
+    }
+
+    impl AsyncWrite for AsyncSimulator {
+        fn poll_write(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+            Poll::Ready(self.simulator.write(buf))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn commands_behave_like_synchronous_device() {
+        let mut device = AsyncDevice::from_stream(AsyncSimulator::new(Simulator::new())).await.unwrap();
+
+        device.xpower_on().await.unwrap();
+        device.xlok(3, 50, XLokOptions::default()).await.unwrap();
+
+        let status = device.xlok_status(3).await.unwrap();
+        assert_eq!(status.speed, 50);
+
+        let version = device.xversion().await.unwrap();
+        assert_eq!(version.major, 2);
+
+        let result = device.xlok(0, 10, XLokOptions::default()).await;
+        match result {
+            Err(ref err) if err.reply() == Some(P50XReply::BadLokParameter) => (),
+            _ => panic!("Unexpected result {:?}", result)
+        }
+
+        assert!(device.into_stream().simulator.power());
+    }
+
+    // only compiles if the futures of generic devices can be spawned
+    fn spawn_power_on<D: AsyncP50XBinary + Send + 'static>(mut device: D) -> tokio::task::JoinHandle<D> {
+        tokio::spawn(async move {
+            device.xpower_on().await.unwrap();
+
+            device
+        })
+    }
+
+    #[tokio::test]
+    async fn dropped_commands_keep_replies_in_sync() {
+        let stream = AsyncSimulator::new(Simulator::new());
+        let held = stream.held.clone();
+
+        let device = AsyncDevice::from_stream(stream).await.unwrap();
+        let mut device = spawn_power_on(device).await.unwrap();
+
+        // the command is sent and dropped before its reply is read
+        held.store(true, Ordering::SeqCst);
+
+        let mut command = Box::pin(device.xlok(3, 20, XLokOptions::default()));
+        let result = std::future::poll_fn(|cx| Poll::Ready(command.as_mut().poll(cx))).await;
+        assert!(result.is_pending());
+        drop(command);
+
+        held.store(false, Ordering::SeqCst);
+
+        assert!(device.xstatus().await.unwrap().power);
+        assert_eq!(device.xlok_status(3).await.unwrap().speed, 20);
+    }
+
+    #[tokio::test]
+    async fn missing_reply_times_out() {
+        let mut device = AsyncDevice::from_stream_unverified(AsyncSimulator::new(Simulator::new()));
+        device.set_timeout(10);
+
+        // wait for a reply without sending anything
+        let result = device.run(|device| device.legacy_command(&[], 1)).await;
+        match result {
+            Err(Error::Timeout) => (),
+            _ => panic!("Unexpected result {:?}", result)
+        }
+    }
+
+    #[test]
+    fn devices_are_created_outside_of_a_runtime() {
+        let mut device = AsyncDevice::from_stream_unverified(AsyncSimulator::new(Simulator::new()));
+
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        runtime.block_on(device.xnop()).unwrap();
+    }
+}
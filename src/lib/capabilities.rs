@@ -0,0 +1,144 @@
+/*
+ * File: capabilities.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::fmt;
+use std::ops::{BitOr, BitOrAssign};
+
+use super::error::*;
+use super::protocol::P50XBinary;
+
+/// Set of optional command groups a command station supports.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    pub const EVENTS: Capabilities = Capabilities(0x01);
+    pub const PROGRAMMING_TRACK: Capabilities = Capabilities(0x02);
+    pub const PROGRAMMING_ON_MAIN: Capabilities = Capabilities(0x04);
+    pub const EXTENDED_FUNCTIONS: Capabilities = Capabilities(0x08);
+    pub const FUNCTIONS_34: Capabilities = Capabilities(0x10);
+    pub const S88_TIMERS: Capabilities = Capabilities(0x20);
+    pub const LOK_CONFIG: Capabilities = Capabilities(0x40);
+    pub const ASCII: Capabilities = Capabilities(0x80);
+
+    const NAMES: [(Capabilities, &'static str); 8] = [
+        (Capabilities::EVENTS, "Events"),
+        (Capabilities::PROGRAMMING_TRACK, "Programming track"),
+        (Capabilities::PROGRAMMING_ON_MAIN, "Programming on the main"),
+        (Capabilities::EXTENDED_FUNCTIONS, "Extended functions"),
+        (Capabilities::FUNCTIONS_34, "Functions 17-28"),
+        (Capabilities::S88_TIMERS, "S88 timers"),
+        (Capabilities::LOK_CONFIG, "Locomotive configuration"),
+        (Capabilities::ASCII, "ASCII mode")
+    ];
+
+    pub fn empty() -> Capabilities {
+        Capabilities(0)
+    }
+
+    pub fn all() -> Capabilities {
+        let mut result = Capabilities::empty();
+
+        for (capability, _) in Capabilities::NAMES.iter() {
+            result |= *capability;
+        }
+
+        return result;
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(&self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn union(self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 | other.0)
+    }
+
+    pub fn insert(&mut self, other: Capabilities) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Capabilities) {
+        self.0 &= !other.0;
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Capabilities;
+
+    fn bitor(self, other: Capabilities) -> Capabilities {
+        self.union(other)
+    }
+}
+
+impl BitOrAssign for Capabilities {
+    fn bitor_assign(&mut self, other: Capabilities) {
+        self.0 |= other.0;
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines: Vec<String> = Capabilities::NAMES
+            .iter()
+            .map(|(capability, name)| format!("{}: {}", name, self.contains(*capability)))
+            .collect();
+
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+// Unknown capabilities are assumed to be supported and left for the device to reject
+pub(crate) fn supports<D: P50XBinary + ?Sized>(device: &D, capability: Capabilities) -> bool {
+    match device.capabilities() {
+        Some(capabilities) => capabilities.contains(capability),
+        None => true
+    }
+}
+
+pub(crate) fn require<D: P50XBinary + ?Sized>(device: &D, capability: Capabilities) -> Result<()> {
+    if supports(device, capability) == false {
+        let names: Vec<&str> = Capabilities::NAMES
+            .iter()
+            .filter(|(known, _)| capability.contains(*known))
+            .map(|(_, name)| *name)
+            .collect();
+
+        return Err(Error::Unsupported(names.join(", ")));
+    }
+
+    return Ok(());
+}
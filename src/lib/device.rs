@@ -26,160 +26,688 @@
  * SOFTWARE.
  */
 
-use serial_unit_testing::serial::*;
-use serial_unit_testing::utils::{TextFormat, bytes_from_hex_string, radix_string};
-use safe_transmute::{guarded_transmute_to_bytes_pod, guarded_transmute_pod};
-
+use std::io;
+use std::thread;
+use std::time::Duration;
+use serialport::SerialPort;
+use log::warn;
+
+use super::ascii::{P50XAscii, AsciiReply};
+use super::capabilities::Capabilities;
 use super::error::*;
+use super::legacy::P50Legacy;
+use super::outcome::{Outcome, WARNING_REPLIES};
+use super::frame::Frame;
+use super::opcode;
 use super::protocol::*;
 use super::reply::P50XReply;
+use super::retry::RetryPolicy;
+use super::trace;
+use super::transport::{Transport, open_serial};
+use super::version::VersionInfo;
+
+const READ_BUFFER_SIZE: usize = 256;
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(1000);
+pub(crate) const PROBE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Baud rates tried by `Device::autodetect`, the most common ones first.
+pub const BAUD_RATES: [u32; 7] = [19200, 38400, 9600, 2400, 4800, 57600, 115200];
+
+// Number of times pending input is dropped and XNOP is sent until the device replies as expected
+pub(crate) const RESYNCHRONIZE_ATTEMPTS: usize = 3;
 
-pub struct Device {
-    serial: Serial,
-    check_settings: CheckSettings,
+// Extended characters tried when the device does not answer to the current one
+const EXTENDED_CHARACTERS: [u8; 3] = [0x58, 0x78, 0xFF];
+
+pub struct Device<T: Transport = Box<dyn SerialPort>> {
+    transport: T,
     extended_character: u8,
-    read_buffer: Vec<u8>
+    timeout: Duration,
+    capabilities: Option<Capabilities>,
+    retry_policy: RetryPolicy,
+    last_command: Vec<u8>,
+    ascii_command: bool,
+    synchronized: bool,
+    auto_resynchronize: bool,
+    traced: bool,
+    connected: bool,
+    pending_events: [u8; 3],
+    pending_lok_events: Vec<XLokEvent>,
+    read_buffer: [u8; READ_BUFFER_SIZE],
+    read_position: usize,
+    read_length: usize
 }
 
 impl Device {
     pub fn new(port_name: &str, baud_rate: u32) -> Result<Device> {
-        let settings = settings::Settings {
-            baud_rate,
-            timeout: 1000,
-            ..Default::default()
-        };
+        let serial = open_serial(port_name, baud_rate)?;
+
+        return Device::from_transport(serial);
+    }
 
-        let mut serial = Serial::open_with_settings(port_name, settings)?;
+    pub fn new_unverified(port_name: &str, baud_rate: u32) -> Result<Device> {
+        let serial = open_serial(port_name, baud_rate)?;
 
-        // verify device is p50x device
-        let check_settings = CheckSettings {
-            ignore_case: true,
-            input_format: TextFormat::Hex,
-            output_format: TextFormat::Hex
-        };
+        return Ok(Device::from_transport_unverified(serial));
+    }
+
+    /// Open the serial port with each of the `BAUD_RATES` until the device answers P50X commands.
+    pub fn autodetect(port_name: &str) -> Result<(Device, u32)> {
+        return Device::autodetect_with(|baud_rate| open_serial(port_name, baud_rate));
+    }
+}
+
+impl<T: Transport> Device<T> {
+    pub fn from_transport(transport: T) -> Result<Device<T>> {
+        let mut device = Device::from_transport_unverified(transport);
 
-        let verified = Device::verify_connection(&mut serial, &check_settings)?;
+        // verify device is p50x device
+        let verified = device.verify_connection()?;
         if verified == false {
             return Err(Error::UnknownDevice);
         }
 
-        return Ok(Device {
-            serial,
-            check_settings,
+        return Ok(device);
+    }
+
+    /// Create a device without verifying it understands P50X, e.g. for legacy P50 interfaces.
+    pub fn from_transport_unverified(transport: T) -> Device<T> {
+        Device {
+            transport,
             extended_character: 0x58,
-            read_buffer: Vec::new()
-        });
+            timeout: DEFAULT_TIMEOUT,
+            capabilities: None,
+            retry_policy: RetryPolicy::none(),
+            last_command: Vec::new(),
+            ascii_command: false,
+            synchronized: true,
+            auto_resynchronize: true,
+            traced: true,
+            connected: true,
+            pending_events: [0; 3],
+            pending_lok_events: Vec::new(),
+            read_buffer: [0; READ_BUFFER_SIZE],
+            read_position: 0,
+            read_length: 0
+        }
+    }
+
+    /// Create a device and negotiate the connection, see `negotiate`.
+    pub fn connect(transport: T, switch_to_binary: bool) -> Result<(Device<T>, ConnectionInfo)> {
+        let mut device = Device::from_transport_unverified(transport);
+        let info = device.negotiate(switch_to_binary)?;
+
+        return Ok((device, info));
+    }
+
+    /// Open a transport with each of the `BAUD_RATES` until the device answers P50X commands.
+    pub fn autodetect_with<F>(mut open: F) -> Result<(Device<T>, u32)> where F: FnMut(u32) -> Result<T> {
+        for baud_rate in BAUD_RATES.iter() {
+            let mut device = Device::from_transport_unverified(open(*baud_rate)?);
+
+            device.transport.set_timeout(PROBE_TIMEOUT)?;
+            let found = device.probe_binary()?.is_some();
+            device.transport.set_timeout(device.timeout)?;
+
+            if found {
+                return Ok((device, *baud_rate));
+            }
+        }
+
+        return Err(Error::UnknownDevice);
     }
 
     pub fn set_timeout(&mut self, timeout: u64) -> Result<()> {
-        match self.serial.set_timeout(timeout) {
-            Ok(_) => Ok(()),
-            Err(err) => Err(Error::from(err))
+        self.timeout = Duration::from_millis(timeout);
+
+        self.transport.set_timeout(self.timeout)
+    }
+
+    /// Probe whether the device speaks P50Xb, P50Xa or the original P50 and detect the active extended character.
+    ///
+    /// A device in ASCII mode is switched to binary commands if `switch_to_binary` is set.
+    pub fn negotiate(&mut self, switch_to_binary: bool) -> Result<ConnectionInfo> {
+        self.transport.set_timeout(PROBE_TIMEOUT)?;
+
+        let result = self.probe_mode(switch_to_binary);
+
+        self.transport.set_timeout(self.timeout)?;
+
+        return result;
+    }
+
+    /// Set when commands rejected with a transient reply, like a full command stack, are sent again.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Whether replies are known to match their commands, see `resynchronize`.
+    pub fn is_synchronized(&self) -> bool {
+        self.synchronized
+    }
+
+    /// Set whether the reply stream is resynchronized before the next command once it got out of sync, enabled by
+    /// default.
+    pub fn set_auto_resynchronize(&mut self, auto_resynchronize: bool) {
+        self.auto_resynchronize = auto_resynchronize;
+    }
+
+    /// Drop all pending input and check with XNOP that replies match their commands again, returns the dropped bytes.
+    ///
+    /// The reply stream gets out of sync when a reply timed out and might still arrive, an unknown reply code was
+    /// received or a reply was longer than expected.
+    pub fn resynchronize(&mut self) -> Result<Vec<u8>> {
+        let mut discarded = self.read_buffer[self.read_position..self.read_length].to_vec();
+
+        self.transport.set_timeout(PROBE_TIMEOUT)?;
+        let result = self.resynchronize_stream(&mut discarded);
+        self.transport.set_timeout(self.timeout)?;
+
+        if result? == false {
+            warn!("Reply stream could not be resynchronized, dropped {:02X?}", discarded);
+
+            return Err(Error::Desynchronized);
+        }
+
+        warn!("Reply stream was resynchronized, dropped {:02X?}", discarded);
+        self.synchronized = true;
+
+        return Ok(discarded);
+    }
+
+    fn resynchronize_stream(&mut self, discarded: &mut Vec<u8>) -> Result<bool> {
+        for _ in 0..RESYNCHRONIZE_ATTEMPTS {
+            discarded.append(&mut self.probe(&[])?);
+
+            let mut reply = self.probe(self.command(opcode::XNOP).as_bytes())?;
+            if reply == [0x00] {
+                return Ok(true);
+            }
+
+            discarded.append(&mut reply);
         }
+
+        return Ok(false);
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Mark the connection as lost, all commands fail with `Error::Disconnected` until `reconnect` succeeds.
+    pub fn disconnect(&mut self) {
+        self.connected = false;
     }
 
-    fn send_u8(&mut self, value: u8) -> Result<()> {
-        let request = radix_string(&[value], &self.check_settings.input_format)?;
-        self.serial.write_format(&request, self.check_settings.input_format)?;
+    /// Continue over a newly opened transport with the current settings, e.g. after the device was unplugged.
+    ///
+    /// The connection is negotiated again, so a restarted device is switched back to binary commands and the
+    /// configured extended character.
+    pub fn reconnect(&mut self, transport: T) -> Result<()> {
+        self.transport = transport;
+        self.transport.set_timeout(self.timeout)?;
+
+        self.read_position = 0;
+        self.read_length = 0;
+        self.synchronized = true;
+        self.connected = true;
+
+        if let Err(err) = self.restore_connection() {
+            self.connected = false;
+
+            return Err(err);
+        }
 
         return Ok(());
     }
 
-    fn send_u16(&mut self, value: u16) -> Result<()> {
-        let data = guarded_transmute_to_bytes_pod::<u16>(&value);
-        let request = radix_string(data, &self.check_settings.input_format)?;
-        self.serial.write_format(&request, self.check_settings.input_format)?;
+    fn restore_connection(&mut self) -> Result<()> {
+        let extended_character = self.extended_character;
+
+        // negotiation switches to the extended character the device answers to
+        let info = self.negotiate(true)?;
+        if info.extended_character.is_none() {
+            return Err(Error::UnknownDevice);
+        }
+
+        if self.extended_character != extended_character {
+            self.xp50xch(extended_character)?;
+        }
 
         return Ok(());
     }
 
-    fn send_u32(&mut self, value: u32) -> Result<()> {
-        let data = guarded_transmute_to_bytes_pod::<u32>(&value);
-        let request = radix_string(data, &self.check_settings.input_format)?;
-        self.serial.write_format(&request, self.check_settings.input_format)?;
+    pub fn extended_character(&self) -> u8 {
+        self.extended_character
+    }
+
+    pub fn into_transport(self) -> T {
+        self.transport
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Drop buffered reply bytes, so the next command reads from the transport again.
+    #[cfg(feature = "async")]
+    pub(crate) fn reset_read_buffer(&mut self) {
+        self.read_position = 0;
+        self.read_length = 0;
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn set_synchronized(&mut self, synchronized: bool) {
+        self.synchronized = synchronized;
+    }
+
+    /// Set whether exchanged data is traced, the async device traces the data of its stream instead.
+    #[cfg(feature = "async")]
+    pub(crate) fn set_traced(&mut self, traced: bool) {
+        self.traced = traced;
+    }
+
+    fn trace_outgoing(&self, data: &[u8]) {
+        if self.traced {
+            trace::outgoing(data, self.extended_character);
+        }
+    }
+
+    fn trace_incoming(&self, data: &[u8]) {
+        if self.traced {
+            trace::incoming(data);
+        }
+    }
+
+    fn command(&self, opcode: u8) -> Frame {
+        Frame::new(self.extended_character, opcode)
+    }
+
+    fn send(&mut self, frame: Frame) -> Result<()> {
+        return self.send_command(frame.as_bytes(), false);
+    }
+
+    // Send a P50Xb frame or a P50Xa command line
+    fn send_command(&mut self, data: &[u8], ascii: bool) -> Result<()> {
+        self.check_connected()?;
+
+        // bytes left from the previous reply mean it was longer than expected
+        if self.read_position < self.read_length {
+            self.synchronized = false;
+        }
+
+        if self.synchronized == false && self.auto_resynchronize {
+            self.resynchronize()?;
+        }
+
+        // remember the command to send it again if it is rejected and to report it with errors
+        self.last_command.clear();
+        self.last_command.extend_from_slice(data);
+        self.ascii_command = ascii;
+
+        self.trace_outgoing(data);
+        self.transport.write_all(data).map_err(|err| self.command_error(Error::from(err)))?;
 
         return Ok(());
     }
 
-    fn send_x(&mut self) -> Result<()> {
-        self.send_u8(self.extended_character)
+    fn check_connected(&self) -> Result<()> {
+        if self.connected == false {
+            return Err(Error::Disconnected);
+        }
+
+        return Ok(());
     }
 
-    fn recv(&mut self, length: usize) -> Result<Vec<u8>> {
-        // if requested data is already completely read
-        if self.read_buffer.len() >= length {
-            let mut data: Vec<u8> = Vec::new();
+    // Errors are reported together with the last sent command, because every read belongs to it
+    fn fill_read_buffer(&mut self) -> Result<()> {
+        let count = match self.transport.read(&mut self.read_buffer) {
+            Ok(count) => count,
+            Err(err) => {
+                // the reply might still arrive and would be read by the next command
+                let err = Error::from(err);
+                if err.is_timeout() {
+                    self.synchronized = false;
+                }
 
-            for _ in 0..length {
-                data.push(self.read_buffer[0]);
-                self.read_buffer.remove(0);
+                return Err(self.command_error(err));
             }
+        };
+        if count == 0 {
+            let err = Error::from(io::Error::from(io::ErrorKind::UnexpectedEof));
 
-            return Ok(data);
+            return Err(self.command_error(err));
         }
 
-        let actual_length = length - self.read_buffer.len();
+        self.trace_incoming(&self.read_buffer[..count]);
+
+        self.read_position = 0;
+        self.read_length = count;
+
+        return Ok(());
+    }
 
-        let response = self.serial.read_min_str_with_format(actual_length, self.check_settings.output_format)?;
-        let mut data = bytes_from_hex_string(&response)?;
+    fn recv_into(&mut self, data: &mut [u8]) -> Result<()> {
+        let mut index = 0;
 
-        // insert read_buffer
-        if self.read_buffer.is_empty() && data.len() > length {
-            self.read_buffer = data.split_off(length);
-        } else if self.read_buffer.is_empty() == false {
-            for i in 0..self.read_buffer.len() {
-                data.insert(i, self.read_buffer[i]);
+        while index < data.len() {
+            if self.read_position == self.read_length {
+                self.fill_read_buffer()?;
             }
+
+            let count = (data.len() - index).min(self.read_length - self.read_position);
+            data[index..index + count].copy_from_slice(&self.read_buffer[self.read_position..self.read_position + count]);
+
+            index += count;
+            self.read_position += count;
         }
 
+        return Ok(());
+    }
+
+    fn recv(&mut self, length: usize) -> Result<Vec<u8>> {
+        let mut data = vec![0; length];
+        self.recv_into(&mut data)?;
+
         return Ok(data);
     }
 
     fn recv_u8(&mut self) -> Result<u8> {
-        let data = self.recv(1)?;
+        if self.read_position == self.read_length {
+            self.fill_read_buffer()?;
+        }
 
-        return Ok(data[0]);
+        let data = self.read_buffer[self.read_position];
+        self.read_position += 1;
+
+        return Ok(data);
     }
 
     fn recv_u16(&mut self) -> Result<u16> {
-        let data = self.recv(2)?;
-        let result = guarded_transmute_pod(&data).unwrap();
+        let mut data = [0; 2];
+        self.recv_into(&mut data)?;
 
-        return Ok(result);
+        return Ok(u16::from_le_bytes(data));
     }
 
-    fn recv_reply(&mut self) -> Result<P50XReply> {
-        let data = self.recv_u8()?;
+    fn xrecv(&mut self, valid_responses: &[P50XReply]) -> Result<P50XReply> {
+        let mut attempt = 1;
+
+        loop {
+            let data = self.recv_u8()?;
+            let response = P50XReply::from(data);
+            if self.traced {
+                trace::reply(response);
+            }
+
+            if valid_responses.contains(&response) {
+                return Ok(response);
+            }
 
-        return Ok(data.into());
+            if attempt >= self.retry_policy.max_attempts || self.retry_policy.is_retryable(response) == false {
+                let error = if let P50XReply::Unknown(data) = response {
+                    self.synchronized = false;
+
+                    Error::UnknownReply(data)
+                } else {
+                    Error::Reply(response)
+                };
+
+                return Err(self.command_error(error));
+            }
+
+            // rejected commands are not executed and the reply has no further data, so the command can be sent again
+            thread::sleep(self.retry_policy.delay(attempt));
+            attempt += 1;
+
+            self.trace_outgoing(&self.last_command);
+            self.transport.write_all(&self.last_command).map_err(|err| self.command_error(Error::from(err)))?;
+        }
     }
 
-    fn xrecv(&mut self, valid_responses: &[P50XReply]) -> Result<P50XReply> {
-        let response = self.recv_reply()?;
+    // Add the last sent command to an error
+    fn command_error(&self, error: Error) -> Error {
+        if self.ascii_command && self.last_command.is_empty() == false {
+            let command = String::from_utf8_lossy(&self.last_command);
+
+            return Error::ascii_command(command.trim_end(), error);
+        }
 
-        if valid_responses.contains(&response) == false {
-            return Err(Error::from(response));
+        if self.last_command.len() < 2 {
+            return error;
         }
 
-        return Ok(response);
+        return Error::command(self.last_command[1], &self.last_command[2..], error);
     }
 
     fn xrecv_ok(&mut self) -> Result<P50XReply> {
         self.xrecv(&[P50XReply::Ok])
     }
 
-    fn verify_connection(serial: &mut Serial, check_settings: &CheckSettings) -> Result<bool> {
-        let (xresult, _) = serial.check_with_settings("58C4", "00", check_settings)?;
-        let (result, _) = serial.check_with_settings("C4", "0000", check_settings)?;
+    // Receive the reply of a command which may be accepted with a warning
+    fn xrecv_outcome(&mut self) -> Result<Outcome<()>> {
+        let mut valid_responses = vec![P50XReply::Ok];
+        valid_responses.extend_from_slice(&WARNING_REPLIES);
+
+        let response = self.xrecv(&valid_responses)?;
+
+        return Ok(Outcome::from_reply((), response));
+    }
+
+    /// Test which optional commands the device implements and remember them for higher level commands.
+    ///
+    /// Commands are only sent as requests or with invalid parameters, so nothing changes on the layout. Events read
+    /// while probing are kept and returned by the next event commands.
+    pub fn probe_capabilities(&mut self) -> Result<Capabilities> {
+        self.transport.set_timeout(PROBE_TIMEOUT)?;
+
+        let result = self.probe_commands();
+
+        self.transport.set_timeout(self.timeout)?;
+
+        let capabilities = result?;
+        self.capabilities = Some(capabilities);
+
+        return Ok(capabilities);
+    }
+
+    /// Use known capabilities without probing, e.g. the ones of `VersionInfo::capabilities`. `None` leaves every
+    /// command to the device.
+    pub fn set_capabilities(&mut self, capabilities: Option<Capabilities>) {
+        self.capabilities = capabilities;
+    }
+
+    fn probe_commands(&mut self) -> Result<Capabilities> {
+        let mut capabilities = Capabilities::empty();
+
+        // event flags have no reply code and only pending locomotive events have the same value as an unknown
+        // command, these are told apart by reading the locomotive events
+        let frame = self.command(opcode::XEVENT);
+        let reply = self.probe(frame.as_bytes())?;
+
+        if reply == [u8::from(P50XReply::BadCommand)] {
+            let result = self.xevt_lok();
+
+            if let Ok(ref events) = result {
+                self.pending_lok_events = events.clone();
+                self.pending_events[0] |= 0x01;
+            }
+
+            self.probe_command(&mut capabilities, Capabilities::EVENTS, result.map(|_| ()))?;
+        } else if reply.is_empty() == false {
+            // flags are reset by reading them, so they are kept for the next xevent
+            for (pending, value) in self.pending_events.iter_mut().zip(reply.iter()) {
+                *pending |= value & 0x7F;
+            }
+
+            capabilities.insert(Capabilities::EVENTS);
+        }
+
+        let result = self.xpt_dcc_read_byte(0);
+        self.probe_command(&mut capabilities, Capabilities::PROGRAMMING_TRACK, result)?;
+
+        // neither address nor cv exist, so the command can not reach a decoder
+        let result = self.xpom_read(0xFFFF, 0).map(|_| ());
+        self.probe_command(&mut capabilities, Capabilities::PROGRAMMING_ON_MAIN, result)?;
+
+        // a device which does not check the parameters has started reading, so the programming track is always left
+        let result = self.xpt_term();
+        self.probe_command(&mut capabilities, Capabilities::PROGRAMMING_TRACK, result)?;
+
+        let result = self.xfuncx_status(0).map(|_| ());
+        self.probe_command(&mut capabilities, Capabilities::EXTENDED_FUNCTIONS, result)?;
+
+        let result = self.xfunc34_status(0).map(|_| ());
+        self.probe_command(&mut capabilities, Capabilities::FUNCTIONS_34, result)?;
+
+        let result = self.xs88_timer(0, false).map(|_| ());
+        self.probe_command(&mut capabilities, Capabilities::S88_TIMERS, result)?;
+
+        let result = self.xlok_config(0).map(|_| ());
+        self.probe_command(&mut capabilities, Capabilities::LOK_CONFIG, result)?;
+
+        // text could be read as original P50 commands by the device, so P50Xa support is taken from the version
+        if let Some(known) = self.xversion()?.capabilities() {
+            if known.contains(Capabilities::ASCII) {
+                capabilities.insert(Capabilities::ASCII);
+            }
+        }
+
+        return Ok(capabilities);
+    }
+
+    // Any reply except an unknown command proves the command exists, even if its parameters were rejected
+    fn probe_command(&mut self, capabilities: &mut Capabilities, capability: Capabilities, result: Result<()>) -> Result<()> {
+        match result {
+            Ok(_) => capabilities.insert(capability),
+            Err(ref err) if err.reply() == Some(P50XReply::BadCommand) => (),
+            Err(ref err) if err.reply().is_some() => capabilities.insert(capability),
+            Err(ref err) if err.is_timeout() => {
+                // drop a late reply before the next command
+                self.probe(&[])?;
+            },
+            Err(err) => return Err(err)
+        };
+
+        return Ok(());
+    }
+
+    fn probe_mode(&mut self, switch_to_binary: bool) -> Result<ConnectionInfo> {
+        if let Some(extended_character) = self.probe_binary()? {
+            return Ok(ConnectionInfo {
+                mode: ConnectionMode::Binary,
+                extended_character: Some(extended_character),
+                switched_to_binary: false
+            });
+        }
+
+        // without P50X only the plain s88 read of module 4 is answered
+        if self.probe(&[0xC4])?.len() == 2 {
+            return Ok(ConnectionInfo {
+                mode: ConnectionMode::Legacy,
+                extended_character: None,
+                switched_to_binary: false
+            });
+        }
+
+        // finish the incomplete line of the previous probes before asking for the version
+        self.probe(b"\r")?;
+
+        if self.probe(b"V\r")?.last() != Some(&b']') {
+            return Err(Error::UnknownDevice);
+        }
+
+        if switch_to_binary == false {
+            return Ok(ConnectionInfo {
+                mode: ConnectionMode::Ascii,
+                extended_character: None,
+                switched_to_binary: false
+            });
+        }
+
+        self.probe(b"ZzA1\r")?;
+
+        match self.probe_binary()? {
+            Some(extended_character) => Ok(ConnectionInfo {
+                mode: ConnectionMode::Ascii,
+                extended_character: Some(extended_character),
+                switched_to_binary: true
+            }),
+            None => Err(Error::UnknownDevice)
+        }
+    }
+
+    fn probe_binary(&mut self) -> Result<Option<u8>> {
+        let mut candidates = vec![self.extended_character];
+        candidates.extend(EXTENDED_CHARACTERS.iter().filter(|value| **value != self.extended_character));
+
+        for extended_character in candidates {
+            let reply = self.probe(&[extended_character, opcode::XNOP])?;
+
+            if reply == [0x00] {
+                self.extended_character = extended_character;
+
+                return Ok(Some(extended_character));
+            }
+
+            // in binary mode the plain nop after a wrong extended character is answered, silence means the bytes
+            // started a P50Xa command line which is left for the ASCII probe to finish
+            if reply.is_empty() {
+                break;
+            }
+        }
+
+        return Ok(None);
+    }
+
+    // Send data and collect everything received until the device stays silent
+    fn probe(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.read_position = 0;
+        self.read_length = 0;
+
+        self.trace_outgoing(data);
+        self.transport.write_all(data)?;
+
+        let mut reply = Vec::new();
+
+        loop {
+            match self.transport.read(&mut self.read_buffer) {
+                Ok(0) => return Err(Error::from(io::Error::from(io::ErrorKind::UnexpectedEof))),
+                Ok(count) => {
+                    self.trace_incoming(&self.read_buffer[..count]);
+                    reply.extend_from_slice(&self.read_buffer[..count]);
+                },
+                Err(err) if err.kind() == io::ErrorKind::TimedOut || err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(Error::from(err))
+            }
+        }
+
+        return Ok(reply);
+    }
+
+    pub(crate) fn verify_connection(&mut self) -> Result<bool> {
+        self.send(self.command(opcode::XNOP))?;
+        let xresult = self.recv(1)? == [0x00];
+
+        // the p50 command is no p50x frame to report errors with
+        self.last_command.clear();
+
+        self.trace_outgoing(&[0xC4]);
+        self.transport.write_all(&[0xC4])?;
+        let result = self.recv(2)? == [0x00, 0x00];
 
         return Ok(result && xresult);
     }
 }
 
-impl P50XBinary for Device {
+impl<T: Transport> P50XBinary for Device<T> {
     fn xpower_off(&mut self) -> Result<()> {
-        self.send_x()?;
-        self.send_u8(0xa6)?;
+        self.send(self.command(opcode::XPWR_OFF))?;
 
         self.xrecv_ok()?;
 
@@ -187,8 +715,7 @@ impl P50XBinary for Device {
     }
 
     fn xpower_on(&mut self) -> Result<()> {
-        self.send_x()?;
-        self.send_u8(0xa7)?;
+        self.send(self.command(opcode::XPWR_ON))?;
 
         self.xrecv_ok()?;
 
@@ -196,8 +723,7 @@ impl P50XBinary for Device {
     }
 
     fn xhalt(&mut self) -> Result<()> {
-        self.send_x()?;
-        self.send_u8(0xa5)?;
+        self.send(self.command(opcode::XHALT))?;
 
         self.xrecv_ok()?;
 
@@ -205,10 +731,7 @@ impl P50XBinary for Device {
     }
 
     fn xso_set(&mut self, special_option: u16, value: u8) -> Result<()> {
-        self.send_x()?;
-        self.send_u8(0xa3)?;
-        self.send_u16(special_option)?;
-        self.send_u8(value)?;
+        self.send(self.command(opcode::XSO_SET).u16(special_option).u8(value))?;
 
         self.xrecv_ok()?;
 
@@ -216,9 +739,7 @@ impl P50XBinary for Device {
     }
 
     fn xso_get(&mut self, special_option: u16) -> Result<u8> {
-        self.send_x()?;
-        self.send_u8(0xa4)?;
-        self.send_u16(special_option)?;
+        self.send(self.command(opcode::XSO_GET).u16(special_option))?;
 
         self.xrecv_ok()?;
         let data = self.recv_u8()?;
@@ -226,9 +747,8 @@ impl P50XBinary for Device {
         return Ok(data);
     }
 
-    fn xversion(&mut self) -> Result<Vec<u8>> {
-        self.send_x()?;
-        self.send_u8(0xa0)?;
+    fn xversion(&mut self) -> Result<VersionInfo> {
+        self.send(self.command(opcode::XVER))?;
 
         let mut data: Vec<u8> = Vec::new();
 
@@ -244,22 +764,20 @@ impl P50XBinary for Device {
             data.append(&mut chunk);
         }
 
-        return Ok(data);
+        return VersionInfo::parse(&data);
     }
 
     fn xp50xch(&mut self, extended_character: u8) -> Result<()> {
-        self.send_x()?;
-        self.send_u8(0xa1)?;
-        self.send_u8(extended_character)?;
+        self.send(self.command(opcode::XP50XCH).u8(extended_character))?;
 
         self.xrecv_ok()?;
+        self.extended_character = extended_character;
 
         return Ok(());
     }
 
     fn xstatus(&mut self) -> Result<DeviceStatus> {
-        self.send_x()?;
-        self.send_u8(0xa2)?;
+        self.send(self.command(opcode::XSTATUS))?;
 
         let data = self.recv_u8()?;
 
@@ -275,8 +793,7 @@ impl P50XBinary for Device {
     }
 
     fn xnop(&mut self) -> Result<()> {
-        self.send_x()?;
-        self.send_u8(0xc4)?;
+        self.send(self.command(opcode::XNOP))?;
 
         self.xrecv_ok()?;
 
@@ -284,9 +801,7 @@ impl P50XBinary for Device {
     }
 
     fn xsensor(&mut self, module: u8) -> Result<[bool; 16]> {
-        self.send_x()?;
-        self.send_u8(0x98)?;
-        self.send_u8(module)?;
+        self.send(self.command(opcode::XSENSOR).u8(module))?;
 
         self.xrecv_ok()?;
         let data = self.recv_u16()?;
@@ -303,8 +818,7 @@ impl P50XBinary for Device {
     }
 
     fn xsens_off(&mut self) -> Result<()> {
-        self.send_x()?;
-        self.send_u8(0x99)?;
+        self.send(self.command(opcode::XSENS_OFF))?;
 
         self.xrecv_ok()?;
 
@@ -312,9 +826,7 @@ impl P50XBinary for Device {
     }
 
     fn x88p_get(&mut self, parameter: u8) -> Result<u8> {
-        self.send_x()?;
-        self.send_u8(0x9C)?;
-        self.send_u8(parameter)?;
+        self.send(self.command(opcode::X88P_GET).u8(parameter))?;
 
         self.xrecv_ok()?;
         let data = self.recv_u8()?;
@@ -323,10 +835,7 @@ impl P50XBinary for Device {
     }
 
     fn x88p_set(&mut self, parameter: u8, value: u8) -> Result<()> {
-        self.send_x()?;
-        self.send_u8(0x9D)?;
-        self.send_u8(parameter)?;
-        self.send_u8(value)?;
+        self.send(self.command(opcode::X88P_SET).u8(parameter).u8(value))?;
 
         self.xrecv_ok()?;
 
@@ -334,15 +843,12 @@ impl P50XBinary for Device {
     }
 
     fn xs88_timer(&mut self, timer: u8, reset: bool) -> Result<u16> {
-        self.send_x()?;
-        self.send_u8(0x9E)?;
-
         let mut param = timer & 0x0F;
         if reset {
             param |= 0x80;
         }
 
-        self.send_u8(param)?;
+        self.send(self.command(opcode::XS88_TIM).u8(param))?;
 
         self.xrecv_ok()?;
         let result = self.recv_u16()?;
@@ -351,15 +857,12 @@ impl P50XBinary for Device {
     }
 
     fn xs88_count(&mut self, timer: u8, reset: bool) -> Result<u16> {
-        self.send_x()?;
-        self.send_u8(0x9F)?;
-
         let mut param = timer & 0x0F;
         if reset {
             param |= 0x80;
         }
 
-        self.send_u8(param)?;
+        self.send(self.command(opcode::XS88_CNT).u8(param))?;
 
         self.xrecv_ok()?;
         let result = self.recv_u16()?;
@@ -367,7 +870,7 @@ impl P50XBinary for Device {
         return Ok(result);
     }
 
-    fn xlok(&mut self, address: u16, speed: i8, options: XLokOptions) -> Result<()> {
+    fn xlok(&mut self, address: u16, speed: i8, options: XLokOptions) -> Result<Outcome<()>> {
         // get config byte from options
         let mut config: u8 = 0;
 
@@ -379,11 +882,16 @@ impl P50XBinary for Device {
             config |= 0x40;
         }
 
+        if options.reverse {
+            config |= 0x20;
+        }
+
         if let Some(functions) = options.functions {
             config |= 0x80;
 
-            for i in 0..4 {
-                if functions[3 - i] {
+            // function 1 is the lowest bit, like in the status reply
+            for (i, function) in functions.iter().enumerate() {
+                if *function {
                     config |= 1 << i;
                 }
             }
@@ -403,21 +911,13 @@ impl P50XBinary for Device {
             }
         };
 
-        self.send_x()?;
-        self.send_u8(0x80)?;
-        self.send_u16(address)?;
-        self.send_u8(speed_value as u8)?;
-        self.send_u8(config)?;
+        self.send(self.command(opcode::XLOK).u16(address).u8(speed_value).u8(config))?;
 
-        self.xrecv_ok()?;
-
-        return Ok(());
+        return self.xrecv_outcome();
     }
 
     fn xlok_status(&mut self, address: u16) -> Result<XLokStatus> {
-        self.send_x()?;
-        self.send_u8(0x84)?;
-        self.send_u16(address)?;
+        self.send(self.command(opcode::XLOK_STATUS).u16(address))?;
 
         self.xrecv_ok()?;
         let mut speed = self.recv_u8()? as i8;
@@ -436,15 +936,14 @@ impl P50XBinary for Device {
                 emergency_stop: speed == 1,
                 force: false,
                 light: config & 0x10 != 0,
+                reverse: config & 0x20 != 0,
                 functions: Some([config & 0x01 != 0, config & 0x02 != 0, config & 0x04 != 0, config & 0x08 != 0])
             }
         });
     }
 
     fn xlok_config(&mut self, address: u16) -> Result<XLokConfig> {
-        self.send_x()?;
-        self.send_u8(0x85)?;
-        self.send_u16(address)?;
+        self.send(self.command(opcode::XLOK_CONFIG).u16(address))?;
 
         self.xrecv_ok()?;
         let protocol = self.recv_u8()?;
@@ -464,10 +963,18 @@ impl P50XBinary for Device {
         });
     }
 
+    fn xlok_set_config(&mut self, address: u16, config: XLokConfig) -> Result<()> {
+        let virtual_address = config.virtual_address.unwrap_or(0xFFFF);
+
+        self.send(self.command(opcode::XLOK_SET_CONFIG).u16(address).u8(config.protocol as u8).u8(config.speed_steps).u16(virtual_address))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
     fn xlok_dispatch(&mut self, address: u16) -> Result<Option<u8>> {
-        self.send_x()?;
-        self.send_u8(0x83)?;
-        self.send_u16(address)?;
+        self.send(self.command(opcode::XLOK_DISPATCH).u16(address))?;
 
         if address & 0xFF00 != 0 {
             let result = self.recv_u8()?;
@@ -480,7 +987,7 @@ impl P50XBinary for Device {
         }
     }
 
-    fn xfunc(&mut self, address: u16, functions: [bool; 8]) -> Result<()> {
+    fn xfunc(&mut self, address: u16, functions: [bool; 8]) -> Result<Outcome<()>> {
         let mut value: u8 = 0;
 
         for i in 0..8 {
@@ -489,20 +996,13 @@ impl P50XBinary for Device {
             }
         }
 
-        self.send_x()?;
-        self.send_u8(0x88)?;
-        self.send_u16(address)?;
-        self.send_u8(value)?;
-
-        self.xrecv_ok()?;
+        self.send(self.command(opcode::XFUNC).u16(address).u8(value))?;
 
-        return Ok(());
+        return self.xrecv_outcome();
     }
 
     fn xfunc_status(&mut self, address: u16) -> Result<[bool; 8]> {
-        self.send_x()?;
-        self.send_u8(0x8C)?;
-        self.send_u16(address)?;
+        self.send(self.command(opcode::XFUNC_STATUS).u16(address))?;
 
         self.xrecv_ok()?;
         let data = self.recv_u8()?;
@@ -517,7 +1017,7 @@ impl P50XBinary for Device {
         return Ok(functions);
     }
 
-    fn xfuncx(&mut self, address: u16, functions: [bool; 8]) -> Result<()> {
+    fn xfuncx(&mut self, address: u16, functions: [bool; 8]) -> Result<Outcome<()>> {
         let mut value: u8 = 0;
 
         for i in 0..8 {
@@ -526,20 +1026,13 @@ impl P50XBinary for Device {
             }
         }
 
-        self.send_x()?;
-        self.send_u8(0x89)?;
-        self.send_u16(address)?;
-        self.send_u8(value)?;
-
-        self.xrecv_ok()?;
+        self.send(self.command(opcode::XFUNCX).u16(address).u8(value))?;
 
-        return Ok(());
+        return self.xrecv_outcome();
     }
 
     fn xfuncx_status(&mut self, address: u16) -> Result<[bool; 8]> {
-        self.send_x()?;
-        self.send_u8(0x8D)?;
-        self.send_u16(address)?;
+        self.send(self.command(opcode::XFUNCX_STATUS).u16(address))?;
 
         self.xrecv_ok()?;
         let data = self.recv_u8()?;
@@ -554,7 +1047,35 @@ impl P50XBinary for Device {
         return Ok(functions);
     }
 
-    fn xturnout(&mut self, address: u16, state: bool, options: XTurnoutOptions) -> Result<()> {
+    fn xfunc34(&mut self, address: u16, functions: [bool; 16]) -> Result<Outcome<()>> {
+        let mut value: u16 = 0;
+
+        for (i, function) in functions.iter().enumerate() {
+            if *function {
+                value |= 1 << i;
+            }
+        }
+
+        self.send(self.command(opcode::XFUNC34).u16(address).u16(value))?;
+
+        return self.xrecv_outcome();
+    }
+
+    fn xfunc34_status(&mut self, address: u16) -> Result<[bool; 16]> {
+        self.send(self.command(opcode::XFUNC34_STATUS).u16(address))?;
+
+        self.xrecv_ok()?;
+        let data = self.recv_u16()?;
+        let mut functions: [bool; 16] = [false; 16];
+
+        for (i, function) in functions.iter_mut().enumerate() {
+            *function = data & (1 << i) != 0;
+        }
+
+        return Ok(functions);
+    }
+
+    fn xturnout(&mut self, address: u16, state: bool, options: XTurnoutOptions) -> Result<Outcome<()>> {
         let address_bytes = address.to_le_bytes();
         let mut data = address_bytes[1] & 0x07;
 
@@ -574,19 +1095,13 @@ impl P50XBinary for Device {
             data |= 0x10;
         }
 
-        self.send_x()?;
-        self.send_u8(0x90)?;
-        self.send_u8(address_bytes[0])?;
-        self.send_u8(data)?;
-
-        self.xrecv_ok()?;
+        self.send(self.command(opcode::XTRNT).u8(address_bytes[0]).u8(data))?;
 
-        return Ok(());
+        return self.xrecv_outcome();
     }
 
     fn xturnout_free(&mut self) -> Result<()> {
-        self.send_x()?;
-        self.send_u8(0x93)?;
+        self.send(self.command(opcode::XTRNT_FREE))?;
 
         self.xrecv_ok()?;
 
@@ -594,9 +1109,7 @@ impl P50XBinary for Device {
     }
 
     fn xturnout_status(&mut self, address: u16) -> Result<XTurnoutStatus> {
-        self.send_x()?;
-        self.send_u8(0x94)?;
-        self.send_u16(address)?;
+        self.send(self.command(opcode::XTRNT_STATUS).u16(address))?;
 
         self.xrecv_ok()?;
         let data = self.recv_u8()?;
@@ -611,9 +1124,7 @@ impl P50XBinary for Device {
     }
 
     fn xturnout_group(&mut self, group_address: u8) -> Result<[(bool, bool); 8]> {
-        self.send_x()?;
-        self.send_u8(0x95)?;
-        self.send_u8(group_address)?;
+        self.send(self.command(opcode::XTRNT_GROUP).u8(group_address))?;
 
         self.xrecv_ok()?;
         let state = self.recv_u8()?;
@@ -630,4 +1141,668 @@ impl P50XBinary for Device {
 
         return Ok(result);
     }
+
+    fn xevent(&mut self) -> Result<XEventStatus> {
+        self.send(self.command(opcode::XEVENT))?;
+
+        // each byte signals with its highest bit if another byte follows
+        let mut data = [0u8; 3];
+        data[0] = self.recv_u8()?;
+
+        if data[0] & 0x80 != 0 {
+            data[1] = self.recv_u8()?;
+
+            if data[1] & 0x80 != 0 {
+                data[2] = self.recv_u8()?;
+            }
+        }
+
+        // add events read while probing capabilities
+        for (value, pending) in data.iter_mut().zip(self.pending_events.iter()) {
+            *value |= pending;
+        }
+
+        self.pending_events = [0; 3];
+
+        return Ok(XEventStatus {
+            lok: data[0] & 0x01 != 0,
+            sensor: data[0] & 0x04 != 0,
+            power_off: data[0] & 0x08 != 0,
+            turnout: data[0] & 0x20 != 0,
+            external_short: data[1] & 0x01 != 0,
+            lokmaus_short: data[1] & 0x02 != 0,
+            internal_short: data[1] & 0x04 != 0,
+            booster_short: data[1] & 0x08 != 0,
+            programming_short: data[1] & 0x10 != 0,
+            overheat: data[1] & 0x20 != 0,
+            status: data[1] & 0x40 != 0,
+            programming: data[2] & 0x04 != 0,
+            external_voltage: data[2] & 0x40 != 0
+        });
+    }
+
+    fn xevt_lok(&mut self) -> Result<Vec<XLokEvent>> {
+        self.send(self.command(opcode::XEVT_LOK))?;
+
+        let mut result = std::mem::take(&mut self.pending_lok_events);
+
+        loop {
+            let speed = self.recv_u8()?;
+
+            // end of event list
+            if speed == 0x80 {
+                break;
+            }
+
+            let mut data = [0u8; 4];
+            self.recv_into(&mut data)?;
+
+            let mut functions = [false; 8];
+
+            for (i, function) in functions.iter_mut().enumerate() {
+                *function = data[0] & (1 << i) != 0;
+            }
+
+            let mut speed = speed as i8;
+            let mut real_speed = data[3] as i8;
+
+            if data[2] & 0x80 != 0 {
+                speed = -speed;
+                real_speed = -real_speed;
+            }
+
+            result.push(XLokEvent {
+                address: u16::from_le_bytes([data[1], data[2] & 0x3F]),
+                speed,
+                real_speed,
+                light: data[2] & 0x40 != 0,
+                functions
+            });
+        }
+
+        return Ok(result);
+    }
+
+    fn xevt_turnout(&mut self) -> Result<Vec<XTurnoutEvent>> {
+        self.send(self.command(opcode::XEVT_TRN))?;
+
+        let count = self.recv_u8()?;
+        let mut result = Vec::with_capacity(count as usize);
+
+        for _ in 0..count {
+            let mut data = [0u8; 2];
+            self.recv_into(&mut data)?;
+
+            result.push(XTurnoutEvent {
+                address: u16::from_le_bytes([data[0], data[1] & 0x07]),
+                state: data[1] & 0x80 != 0
+            });
+        }
+
+        return Ok(result);
+    }
+
+    fn xevt_sensor(&mut self) -> Result<Vec<XSensorEvent>> {
+        self.send(self.command(opcode::XEVT_SEN))?;
+
+        let mut result = Vec::new();
+
+        loop {
+            let module = self.recv_u8()?;
+
+            // end of event list
+            if module == 0 {
+                break;
+            }
+
+            let data = self.recv_u16()?;
+            let mut contacts = [false; 16];
+
+            for (i, contact) in contacts.iter_mut().enumerate() {
+                *contact = data & (1 << i) != 0;
+            }
+
+            result.push(XSensorEvent {
+                module,
+                contacts
+            });
+        }
+
+        return Ok(result);
+    }
+
+    fn xevt_pt(&mut self) -> Result<Option<XPTEvent>> {
+        self.send(self.command(opcode::XEVT_PT))?;
+
+        let length = self.recv_u8()?;
+
+        // no programming track event available
+        if length == 0xF5 || length == 0 {
+            return Ok(None);
+        }
+
+        let data = self.recv(length as usize)?;
+
+        return Ok(Some(XPTEvent {
+            status: P50XReply::from(data[0]),
+            value: data.get(1).copied()
+        }));
+    }
+
+    fn xpt_term(&mut self) -> Result<()> {
+        self.send(self.command(opcode::XPT_TERM))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
+    fn xpt_motorola_write_register(&mut self, register: u8, value: u8) -> Result<()> {
+        self.send(self.command(opcode::XPT_MOT_WR_REG).u8(register).u8(value))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
+    fn xpt_dcc_read_register(&mut self, register: u8) -> Result<()> {
+        self.send(self.command(opcode::XPT_DCC_RD_REG).u8(register))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
+    fn xpt_dcc_write_register(&mut self, register: u8, value: u8) -> Result<()> {
+        self.send(self.command(opcode::XPT_DCC_WR_REG).u8(register).u8(value))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
+    fn xpt_dcc_read_page(&mut self, cv: u16) -> Result<()> {
+        self.send(self.command(opcode::XPT_DCC_RD_PG).u16(cv))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
+    fn xpt_dcc_write_page(&mut self, cv: u16, value: u8) -> Result<()> {
+        self.send(self.command(opcode::XPT_DCC_WR_PG).u16(cv).u8(value))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
+    fn xpt_dcc_read_physical_register(&mut self, register: u8) -> Result<()> {
+        self.send(self.command(opcode::XPT_DCC_RD_PHY).u8(register))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
+    fn xpt_dcc_write_physical_register(&mut self, register: u8, value: u8) -> Result<()> {
+        self.send(self.command(opcode::XPT_DCC_WR_PHY).u8(register).u8(value))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
+    fn xpt_dcc_read_byte(&mut self, cv: u16) -> Result<()> {
+        self.send(self.command(opcode::XPT_DCC_RD_BYTE).u16(cv))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
+    fn xpt_dcc_read_bit(&mut self, cv: u16, bit: u8) -> Result<()> {
+        self.send(self.command(opcode::XPT_DCC_RD_BIT).u16(cv).u8(bit & 0x07))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
+    fn xpt_dcc_write_bit(&mut self, cv: u16, bit: u8, value: bool) -> Result<()> {
+        self.send(self.command(opcode::XPT_DCC_WR_BIT).u16(cv).u8(bit & 0x07).u8(value as u8))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
+    fn xpt_dcc_write_byte(&mut self, cv: u16, value: u8) -> Result<()> {
+        self.send(self.command(opcode::XPT_DCC_WR_BYTE).u16(cv).u8(value))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
+    fn xpom_read(&mut self, address: u16, cv: u16) -> Result<u8> {
+        self.send(self.command(opcode::XPOM_RD).u16(address).u16(cv))?;
+
+        self.xrecv_ok()?;
+        let data = self.recv_u8()?;
+
+        return Ok(data);
+    }
+
+    fn xpom_write(&mut self, address: u16, cv: u16, value: u8) -> Result<()> {
+        self.send(self.command(opcode::XPOM_WR).u16(address).u16(cv).u8(value))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
+    fn xpom_write_bit(&mut self, address: u16, cv: u16, bit: u8, value: bool) -> Result<()> {
+        let mut data = bit & 0x07;
+        if value {
+            data |= 0x08;
+        }
+
+        self.send(self.command(opcode::XPOM_WR_BIT).u16(address).u16(cv).u8(data))?;
+
+        self.xrecv_ok()?;
+
+        return Ok(());
+    }
+
+    fn capabilities(&self) -> Option<Capabilities> {
+        self.capabilities
+    }
+}
+
+impl<T: Transport> P50XAscii for Device<T> {
+    fn acommand(&mut self, command: &str) -> Result<AsciiReply> {
+        let mut data = command.as_bytes().to_vec();
+        data.push(b'\r');
+
+        self.send_command(&data, true)?;
+
+        // read until the prompt marks the end of the reply
+        let mut reply = Vec::new();
+
+        loop {
+            let data = self.recv_u8()?;
+
+            if data == b']' {
+                break;
+            }
+
+            reply.push(data);
+        }
+
+        let mut reply = AsciiReply::parse(&String::from_utf8_lossy(&reply));
+        reply.command = command.to_string();
+
+        return Ok(reply);
+    }
+}
+
+impl<T: Transport> P50Legacy for Device<T> {
+    fn legacy_command(&mut self, data: &[u8], reply_length: usize) -> Result<Vec<u8>> {
+        self.check_connected()?;
+        self.last_command.clear();
+
+        self.trace_outgoing(data);
+        self.transport.write_all(data)?;
+
+        return self.recv(reply_length);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::io::{self, Read, Write};
+
+    use super::*;
+    use crate::sim::Simulator;
+    use crate::sim::testing::TestSimulator;
+
+    struct MockTransport {
+        input: VecDeque<u8>,
+        output: Vec<u8>,
+        writes: usize
+    }
+
+    impl MockTransport {
+        fn new(replies: &[u8]) -> MockTransport {
+            // replies to the connection verification come first
+            let mut input: VecDeque<u8> = vec![0x00, 0x00, 0x00].into();
+            input.extend(replies);
+
+            MockTransport {
+                input,
+                output: Vec::new(),
+                writes: 0
+            }
+        }
+    }
+
+    impl Read for MockTransport {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            // replies of all commands are queued up front, so hand out single bytes to not read ahead
+            match self.input.pop_front() {
+                Some(value) => {
+                    buf[0] = value;
+
+                    return Ok(1);
+                },
+                None => return Err(io::Error::from(io::ErrorKind::TimedOut))
+            }
+        }
+    }
+
+    impl Write for MockTransport {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.extend_from_slice(buf);
+            self.writes += 1;
+
+            return Ok(buf.len());
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            return Ok(());
+        }
+    }
+
+    impl Transport for MockTransport {}
+
+    #[test]
+    fn autodetect_tries_baud_rates_until_device_answers() {
+        let (_, baud_rate) = Device::autodetect_with(|baud_rate| {
+            let transport: Box<dyn Transport> = if baud_rate == 2400 {
+                Box::new(Simulator::new())
+            } else {
+                Box::new(MockTransport {
+                    input: VecDeque::new(),
+                    output: Vec::new(),
+                    writes: 0
+                })
+            };
+
+            return Ok(transport);
+        }).unwrap();
+
+        assert_eq!(baud_rate, 2400);
+    }
+
+    #[test]
+    fn probe_capabilities_finds_implemented_commands() {
+        let mut device = Device::from_transport(Simulator::new()).unwrap();
+        assert_eq!(device.capabilities(), None);
+
+        let capabilities = device.probe_capabilities().unwrap();
+        assert_eq!(capabilities, Capabilities::all());
+        assert_eq!(device.capabilities(), Some(Capabilities::all()));
+
+        // the device is still in sync after probing
+        device.xnop().unwrap();
+    }
+
+    #[test]
+    fn probing_leaves_layout_unchanged() {
+        let mut simulator = Simulator::new();
+        simulator.set_power(true);
+
+        let transport = TestSimulator::new(simulator);
+        let opcodes = transport.opcodes.clone();
+
+        let (mut device, _) = Device::connect(transport, false).unwrap();
+        device.probe_capabilities().unwrap();
+
+        // reads on the programming track are always terminated
+        let opcodes = opcodes.lock().unwrap().clone();
+        let term = opcodes.iter().rposition(|opcode| *opcode == 0xE4).unwrap();
+        assert!(opcodes.iter().rposition(|opcode| *opcode == 0xF0).unwrap() < term);
+        assert!(opcodes.iter().rposition(|opcode| *opcode == 0xDD).unwrap() < term);
+        assert!(device.xevent().unwrap().programming == false);
+
+        // no text was read as original P50 commands
+        let simulator = device.into_transport().simulator;
+        for address in 1..=80 {
+            assert_eq!(simulator.lok_speed(address), 0);
+            assert!(simulator.lok_light(address) == false);
+        }
+
+        assert!(simulator.power());
+    }
+
+    #[test]
+    fn probe_capabilities_keeps_pending_events() {
+        let mut simulator = Simulator::new();
+        simulator.set_power(true);
+        simulator.drive_lok(42, 20);
+
+        // a pending locomotive event has the same event flags as an unknown command
+        let mut device = Device::from_transport(simulator).unwrap();
+        assert!(device.probe_capabilities().unwrap().contains(Capabilities::EVENTS));
+
+        assert!(device.xevent().unwrap().lok);
+        let events = device.xevt_lok().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].address, 42);
+
+        assert!(device.xevent().unwrap().any() == false);
+
+        // power off is signaled once
+        device.xpower_off().unwrap();
+        device.probe_capabilities().unwrap();
+        assert!(device.xevent().unwrap().power_off);
+        assert!(device.xevent().unwrap().power_off == false);
+    }
+
+    #[test]
+    fn verify_connection_fails_for_unknown_device() {
+        let transport = MockTransport {
+            input: vec![0x01, 0x00, 0x00].into(),
+            output: Vec::new(),
+            writes: 0
+        };
+
+        match Device::from_transport(transport) {
+            Err(Error::UnknownDevice) => (),
+            _ => panic!("Expected unknown device error")
+        }
+    }
+
+    #[test]
+    fn xlok_sends_command_over_transport() {
+        let mut device = Device::from_transport(MockTransport::new(&[0x00])).unwrap();
+        let options = XLokOptions {
+            light: true,
+            ..Default::default()
+        };
+
+        device.xlok(1234, -20, options).unwrap();
+
+        let transport = device.into_transport();
+        assert_eq!(transport.output[3..], [0x58, 0x80, 0xD2, 0x04, 0x14, 0x30]);
+        assert_eq!(transport.writes, 3);
+    }
+
+    #[test]
+    fn xlok_sends_function_1_in_lowest_bit() {
+        let mut device = Device::from_transport(MockTransport::new(&[0x00, 0x00, 0x0A, 0x01, 0x0A])).unwrap();
+        let options = XLokOptions {
+            functions: Some([true, false, false, false]),
+            ..Default::default()
+        };
+
+        device.xlok(3, 10, options).unwrap();
+
+        // the status reply has function 1 in the lowest bit as well
+        assert_eq!(device.xlok_status(3).unwrap().options.functions, Some([true, false, false, false]));
+
+        let transport = device.into_transport();
+        assert_eq!(transport.output[3..9], [0x58, 0x80, 0x03, 0x00, 0x0A, 0x81]);
+    }
+
+    #[test]
+    fn xsensor_reads_reply_from_transport() {
+        let mut device = Device::from_transport(MockTransport::new(&[0x00, 0x05, 0x80])).unwrap();
+
+        let sensors = device.xsensor(1).unwrap();
+
+        assert_eq!(sensors[..4], [true, false, true, false]);
+        assert!(sensors[15]);
+    }
+
+    #[test]
+    fn transient_replies_are_retried() {
+        let mut device = Device::from_transport(MockTransport::new(&[0x09, 0x80, 0x00])).unwrap();
+        device.set_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            delay: Duration::from_millis(1),
+            ..Default::default()
+        });
+
+        device.xturnout(5, true, XTurnoutOptions::default()).unwrap();
+
+        let transport = device.into_transport();
+        assert_eq!(transport.writes, 5);
+        assert_eq!(transport.output[3..7], transport.output[7..11]);
+        assert_eq!(transport.output[3..7], transport.output[11..]);
+
+        // without retries the first rejection is reported
+        let mut device = Device::from_transport(MockTransport::new(&[0x09])).unwrap();
+
+        let result = device.xturnout(5, true, XTurnoutOptions::default());
+        match result {
+            Err(ref err) if err.reply() == Some(P50XReply::FullTurnoutCommandStack) => (),
+            _ => panic!("Unexpected result {:?}", result)
+        }
+    }
+
+    #[test]
+    fn rejected_commands_report_command_and_raw_reply() {
+        let mut device = Device::from_transport(MockTransport::new(&[0x55])).unwrap();
+
+        let error = device.xlok(3, 10, XLokOptions::default()).unwrap_err();
+        assert_eq!(error.opcode(), Some(0x80));
+        assert_eq!(error.address(), Some(3));
+        assert_eq!(error.reply(), Some(P50XReply::Unknown(0x55)));
+
+        match error.root() {
+            Error::UnknownReply(0x55) => (),
+            root => panic!("Unexpected error {:?}", root)
+        }
+
+        // the mock does not answer the resynchronization
+        let result = device.xnop();
+        match result {
+            Err(Error::Desynchronized) => (),
+            _ => panic!("Unexpected result {:?}", result)
+        }
+    }
+
+    #[test]
+    fn missing_reply_data_reports_command() {
+        let mut device = Device::from_transport(MockTransport::new(&[0x00, 0x05])).unwrap();
+
+        let error = device.xsensor(2).unwrap_err();
+        assert_eq!(error.opcode(), Some(0x98));
+        assert!(error.is_timeout());
+    }
+
+    #[test]
+    fn stray_bytes_are_dropped_before_next_command() {
+        let mut simulator = Simulator::new();
+        simulator.set_power(true);
+
+        let mut device = Device::from_transport(TestSimulator::new(simulator)).unwrap();
+
+        // a late reply is taken for the reply of the next command and leaves its real reply behind
+        device.transport.noise = vec![0x00];
+        device.xhalt().unwrap();
+        assert!(device.is_synchronized());
+
+        device.xpower_off().unwrap();
+        assert!(device.is_synchronized());
+        assert!(device.xstatus().unwrap().power == false);
+
+        // unknown reply codes are reported and dropped
+        device.transport.noise = vec![0x55];
+        let result = device.xpower_on();
+        match result {
+            Err(ref err) if err.reply() == Some(P50XReply::Unknown(0x55)) => (),
+            _ => panic!("Unexpected result {:?}", result)
+        }
+        assert!(device.is_synchronized() == false);
+
+        assert_eq!(device.resynchronize().unwrap(), vec![0x00]);
+        device.xpower_on().unwrap();
+        assert!(device.xstatus().unwrap().power);
+    }
+
+    #[test]
+    fn lok_config_can_be_changed() {
+        let mut device = Device::from_transport(Simulator::new()).unwrap();
+
+        let config = XLokConfig {
+            protocol: XProtocol::Motorola,
+            speed_steps: 14,
+            virtual_address: Some(12)
+        };
+        device.xlok_set_config(3, config).unwrap();
+
+        let config = device.xlok_config(3).unwrap();
+        assert_eq!(config.protocol, XProtocol::Motorola);
+        assert_eq!(config.speed_steps, 14);
+        assert_eq!(config.virtual_address, Some(12));
+
+        let config = XLokConfig {
+            protocol: XProtocol::Motorola,
+            speed_steps: 128,
+            virtual_address: None
+        };
+
+        match device.xlok_set_config(3, config) {
+            Err(ref err) if err.reply() == Some(P50XReply::BadParameter) => (),
+            result => panic!("Unexpected result {:?}", result)
+        }
+    }
+
+    #[test]
+    fn negotiation_detects_mode_and_extended_character() {
+        let (mut device, info) = Device::connect(Simulator::new(), false).unwrap();
+        assert_eq!(info.mode, ConnectionMode::Binary);
+        assert_eq!(info.extended_character, Some(0x58));
+
+        device.xp50xch(0xFF).unwrap();
+        let mut device = Device::from_transport_unverified(device.into_transport());
+        let info = device.negotiate(false).unwrap();
+        assert_eq!(info.extended_character, Some(0xFF));
+        device.xnop().unwrap();
+
+        let mut simulator = Simulator::new();
+        simulator.set_mode(ConnectionMode::Legacy);
+        let (_, info) = Device::connect(simulator, true).unwrap();
+        assert_eq!(info.mode, ConnectionMode::Legacy);
+        assert_eq!(info.extended_character, None);
+
+        let mut simulator = Simulator::new();
+        simulator.set_mode(ConnectionMode::Ascii);
+        let (_, info) = Device::connect(simulator, false).unwrap();
+        assert_eq!(info.mode, ConnectionMode::Ascii);
+        assert!(info.switched_to_binary == false);
+
+        let mut simulator = Simulator::new();
+        simulator.set_mode(ConnectionMode::Ascii);
+        let (mut device, info) = Device::connect(simulator, true).unwrap();
+        assert_eq!(info.mode, ConnectionMode::Ascii);
+        assert_eq!(info.extended_character, Some(0x58));
+        assert!(info.switched_to_binary);
+        device.xpower_on().unwrap();
+        assert_eq!(device.into_transport().mode(), ConnectionMode::Binary);
+    }
 }
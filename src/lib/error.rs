@@ -29,29 +29,144 @@
 use std::error::Error as StdError;
 use std::fmt::{self, Display, Formatter};
 use std::convert::From;
-use serial_unit_testing::error::Error as SerialError;
+use std::io;
 
 use super::reply::P50XReply;
+use super::retry::TRANSIENT_REPLIES;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum Error {
     UnknownDevice,
-    Serial(SerialError),
+    Serial(serialport::Error),
+    Io(io::Error),
+    /// The device did not reply in time.
+    Timeout,
     UnknownResponse(String),
     Reply(P50XReply),
+    /// Reply code which is not part of the protocol.
+    UnknownReply(u8),
+    /// Error of a command sent to the device together with the command which failed.
+    Command {
+        opcode: u8,
+        parameters: Vec<u8>,
+        source: Box<Error>
+    },
+    /// Error of a P50Xa command line sent to the device together with the line which failed.
+    AsciiCommand {
+        command: String,
+        source: Box<Error>
+    },
+    InvalidArgument(String),
+    /// The device does not support the command.
+    Unsupported(String),
+    /// Replies could not be matched to their commands anymore.
+    Desynchronized,
+    /// The connection to the device was lost and is not open again yet.
+    Disconnected,
     Other
 }
 
+impl Error {
+    pub(crate) fn command(opcode: u8, parameters: &[u8], source: Error) -> Error {
+        Error::Command {
+            opcode,
+            parameters: parameters.to_vec(),
+            source: Box::new(source)
+        }
+    }
+
+    pub(crate) fn ascii_command(command: &str, source: Error) -> Error {
+        Error::AsciiCommand {
+            command: command.to_string(),
+            source: Box::new(source)
+        }
+    }
+
+    /// Error without the command context.
+    pub fn root(&self) -> &Error {
+        match *self {
+            Error::Command { ref source, .. } | Error::AsciiCommand { ref source, .. } => source.root(),
+            _ => self
+        }
+    }
+
+    /// Reply code the device rejected the command with, if any.
+    pub fn reply(&self) -> Option<P50XReply> {
+        match *self.root() {
+            Error::Reply(reply) => Some(reply),
+            Error::UnknownReply(value) => Some(P50XReply::Unknown(value)),
+            _ => None
+        }
+    }
+
+    pub fn opcode(&self) -> Option<u8> {
+        match *self {
+            Error::Command { opcode, .. } => Some(opcode),
+            _ => None
+        }
+    }
+
+    /// Address of the locomotive or turnout the failed command was sent for.
+    pub fn address(&self) -> Option<u16> {
+        match *self {
+            Error::Command { opcode: 0x80..=0x8E, ref parameters, .. } if parameters.len() >= 2 => {
+                Some(u16::from_le_bytes([parameters[0], parameters[1]]))
+            },
+            Error::Command { opcode: 0x90, ref parameters, .. } if parameters.len() >= 2 => {
+                Some(u16::from_le_bytes([parameters[0], parameters[1] & 0x07]))
+            },
+            Error::Command { opcode: 0x94 | 0xDD..=0xDF, ref parameters, .. } if parameters.len() >= 2 => {
+                Some(u16::from_le_bytes([parameters[0], parameters[1]]))
+            },
+            _ => None
+        }
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        matches!(*self.root(), Error::Timeout)
+    }
+
+    /// Whether the same command might succeed when it is sent again later.
+    pub fn is_retryable(&self) -> bool {
+        match *self.root() {
+            Error::Timeout | Error::Disconnected => true,
+            Error::Reply(reply) => TRANSIENT_REPLIES.contains(&reply),
+            _ => false
+        }
+    }
+
+    /// Whether the connection to the device is unusable and has to be opened again.
+    pub fn is_fatal(&self) -> bool {
+        matches!(*self.root(), Error::UnknownDevice | Error::Serial(_) | Error::Io(_) | Error::Desynchronized)
+    }
+}
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match *self {
             Error::UnknownDevice => write!(f, "Unknown device"),
-            Error::Serial(ref cause) => write!(f, "Serial Error: {}", cause.to_string()),
+            Error::Serial(ref cause) => write!(f, "Serial Error: {}", cause),
+            Error::Io(ref cause) => write!(f, "I/O Error: {}", cause),
+            Error::Timeout => write!(f, "Device did not reply in time"),
             Error::UnknownResponse(ref cause) => write!(f, "Unknown response: {}", cause),
             Error::Reply(ref cause) => write!(f, "P50X Reply: {:?}", cause),
+            Error::UnknownReply(value) => write!(f, "Unknown P50X reply: 0x{:02X}", value),
+            Error::Command { opcode, ref parameters, ref source } => {
+                write!(f, "{} (command 0x{:02X}", source, opcode)?;
+
+                for parameter in parameters {
+                    write!(f, " {:02X}", parameter)?;
+                }
+
+                write!(f, ")")
+            },
+            Error::AsciiCommand { ref command, ref source } => write!(f, "{} (command '{}')", source, command),
+            Error::InvalidArgument(ref cause) => write!(f, "Invalid argument: {}", cause),
+            Error::Unsupported(ref cause) => write!(f, "Not supported by the device: {}", cause),
+            Error::Desynchronized => write!(f, "Replies do not match their commands"),
+            Error::Disconnected => write!(f, "Device is disconnected"),
             Error::Other => write!(f, "Unknown error")
         }
     }
@@ -61,9 +176,18 @@ impl StdError for Error {
     fn description(&self) -> &str {
         match *self {
             Error::UnknownDevice => "Unknown analyzer device",
-            Error::Serial(ref cause) => cause.description(),
+            Error::Serial(_) => "Serial error",
+            Error::Io(_) => "I/O error",
+            Error::Timeout => "Timeout",
             Error::UnknownResponse(_) => "Unkonwn response",
             Error::Reply(_) => "P50X Reply",
+            Error::UnknownReply(_) => "Unknown P50X reply",
+            Error::Command { .. } => "P50X command failed",
+            Error::AsciiCommand { .. } => "P50Xa command failed",
+            Error::InvalidArgument(_) => "Invalid argument",
+            Error::Unsupported(_) => "Not supported",
+            Error::Desynchronized => "Desynchronized",
+            Error::Disconnected => "Disconnected",
             Error::Other => "Unknown error"
         }
     }
@@ -71,13 +195,25 @@ impl StdError for Error {
     fn cause(&self) -> Option<&dyn StdError> {
         match *self {
             Error::Serial(ref cause) => Some(cause),
+            Error::Io(ref cause) => Some(cause),
+            Error::Command { ref source, .. } | Error::AsciiCommand { ref source, .. } => Some(source.as_ref()),
             _ => None
         }
     }
 }
 
-impl From<SerialError> for Error {
-    fn from(cause: SerialError) -> Error {
+impl From<io::Error> for Error {
+    fn from(cause: io::Error) -> Error {
+        // serial ports time out, sockets with a read timeout would block
+        match cause.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => Error::Timeout,
+            _ => Error::Io(cause)
+        }
+    }
+}
+
+impl From<serialport::Error> for Error {
+    fn from(cause: serialport::Error) -> Error {
         Error::Serial(cause)
     }
 }
@@ -87,3 +223,33 @@ impl From<P50XReply> for Error {
         Error::Reply(cause)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_are_classified() {
+        let error = Error::command(0x80, &[0x03, 0x00, 0x0A, 0x00], Error::Reply(P50XReply::NoLokCommandSpace));
+        assert_eq!(error.opcode(), Some(0x80));
+        assert_eq!(error.address(), Some(3));
+        assert_eq!(error.reply(), Some(P50XReply::NoLokCommandSpace));
+        assert!(error.is_retryable());
+        assert!(error.is_fatal() == false);
+        assert_eq!(error.to_string(), "P50X Reply: NoLokCommandSpace (command 0x80 03 00 0A 00)");
+
+        let error = Error::from(io::Error::from(io::ErrorKind::TimedOut));
+        assert!(error.is_timeout());
+        assert!(error.is_retryable());
+
+        let error = Error::from(io::Error::from(io::ErrorKind::BrokenPipe));
+        assert!(error.is_fatal());
+        assert!(error.is_retryable() == false);
+
+        assert_eq!(Error::UnknownReply(0x55).reply(), Some(P50XReply::Unknown(0x55)));
+        assert!(Error::Reply(P50XReply::BadParameter).is_retryable() == false);
+
+        let error = Error::command(0xDE, &[0xD2, 0x04, 0x03, 0x00, 0x14], Error::Timeout);
+        assert_eq!(error.address(), Some(1234));
+    }
+}
@@ -0,0 +1,60 @@
+/*
+ * File: frame.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+/// Complete P50Xb command frame, built up in a single buffer and written at once.
+pub(crate) struct Frame {
+    data: Vec<u8>
+}
+
+impl Frame {
+    pub fn new(extended_character: u8, opcode: u8) -> Frame {
+        let mut data = Vec::with_capacity(8);
+        data.push(extended_character);
+        data.push(opcode);
+
+        return Frame {
+            data
+        };
+    }
+
+    pub fn u8(mut self, value: u8) -> Frame {
+        self.data.push(value);
+
+        return self;
+    }
+
+    pub fn u16(mut self, value: u16) -> Frame {
+        self.data.extend_from_slice(&value.to_le_bytes());
+
+        return self;
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
@@ -0,0 +1,183 @@
+/*
+ * File: functions.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use super::capabilities::{Capabilities, require, supports};
+use super::error::*;
+use super::outcome::Outcome;
+use super::protocol::{P50XBinary, XLokOptions};
+
+pub const MAX_FUNCTION: u8 = 28;
+
+/// Access to single locomotive functions without having to know their function group.
+///
+/// Function 0 is the locomotive light. Setting a function reads the current state of its group first, so other
+/// functions are kept as they are. Changes made by other throttles between reading and setting a group are
+/// overwritten.
+pub trait LokFunctions: P50XBinary {
+    fn functions(&mut self, address: u16) -> Result<[bool; MAX_FUNCTION as usize + 1]> {
+        let mut result = [false; MAX_FUNCTION as usize + 1];
+
+        result[0] = self.xlok_status(address)?.options.light;
+        result[1..9].copy_from_slice(&self.xfunc_status(address)?);
+
+        // functions the device does not support are always off
+        if supports(self, Capabilities::EXTENDED_FUNCTIONS) {
+            result[9..17].copy_from_slice(&self.xfuncx_status(address)?);
+        }
+
+        if supports(self, Capabilities::FUNCTIONS_34) {
+            result[17..].copy_from_slice(&self.xfunc34_status(address)?[..MAX_FUNCTION as usize - 16]);
+        }
+
+        return Ok(result);
+    }
+
+    fn function(&mut self, address: u16, number: u8) -> Result<bool> {
+        let index = number as usize;
+
+        match number {
+            0 => Ok(self.xlok_status(address)?.options.light),
+            1..=8 => Ok(self.xfunc_status(address)?[index - 1]),
+            9..=16 => {
+                require(self, Capabilities::EXTENDED_FUNCTIONS)?;
+
+                Ok(self.xfuncx_status(address)?[index - 9])
+            },
+            17..=MAX_FUNCTION => {
+                require(self, Capabilities::FUNCTIONS_34)?;
+
+                Ok(self.xfunc34_status(address)?[index - 17])
+            },
+            _ => Err(Error::InvalidArgument(format!("function {}", number)))
+        }
+    }
+
+    /// Switch the light with the given locomotive options.
+    ///
+    /// P50X has no command to only switch the light, so the current speed and direction are read and sent again
+    /// together with the light. A speed set by another throttle in between is overwritten.
+    fn set_light(&mut self, address: u16, on: bool, options: XLokOptions) -> Result<Outcome<()>> {
+        let status = self.xlok_status(address)?;
+        let options = XLokOptions {
+            light: on,
+            reverse: status.options.reverse,
+            ..options
+        };
+
+        return self.xlok(address, status.speed, options);
+    }
+
+    /// Switch a single function, function 0 is switched with `set_light` and default locomotive options.
+    fn set_function(&mut self, address: u16, number: u8, on: bool) -> Result<Outcome<()>> {
+        let index = number as usize;
+
+        match number {
+            0 => self.set_light(address, on, XLokOptions::default()),
+            1..=8 => {
+                let mut functions = self.xfunc_status(address)?;
+                functions[index - 1] = on;
+
+                self.xfunc(address, functions)
+            },
+            9..=16 => {
+                require(self, Capabilities::EXTENDED_FUNCTIONS)?;
+
+                let mut functions = self.xfuncx_status(address)?;
+                functions[index - 9] = on;
+
+                self.xfuncx(address, functions)
+            },
+            17..=MAX_FUNCTION => {
+                require(self, Capabilities::FUNCTIONS_34)?;
+
+                let mut functions = self.xfunc34_status(address)?;
+                functions[index - 17] = on;
+
+                self.xfunc34(address, functions)
+            },
+            _ => Err(Error::InvalidArgument(format!("function {}", number)))
+        }
+    }
+}
+
+impl<D: P50XBinary + ?Sized> LokFunctions for D {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Device;
+    use crate::sim::Simulator;
+
+    #[test]
+    fn set_function_keeps_other_functions() {
+        let mut simulator = Simulator::new();
+        simulator.set_power(true);
+
+        let mut device = Device::from_transport(simulator).unwrap();
+        device.xlok(5, 30, XLokOptions::default()).unwrap();
+
+        for number in &[0, 3, 5, 12, 20, 28] {
+            device.set_function(5, *number, true).unwrap();
+        }
+
+        device.set_function(5, 5, false).unwrap();
+
+        let functions = device.functions(5).unwrap();
+        let enabled: Vec<usize> = (0..functions.len()).filter(|i| functions[*i]).collect();
+        assert_eq!(enabled, vec![0, 3, 12, 20, 28]);
+
+        assert!(device.function(5, 20).unwrap());
+        assert_eq!(device.xlok_status(5).unwrap().speed, 30);
+
+        let options = XLokOptions {
+            force: true,
+            ..Default::default()
+        };
+        device.set_light(5, false, options).unwrap();
+        assert!(device.function(5, 0).unwrap() == false);
+        assert_eq!(device.xlok_status(5).unwrap().speed, 30);
+
+        // stopped locomotives keep driving in reverse
+        let options = XLokOptions {
+            reverse: true,
+            ..Default::default()
+        };
+        device.xlok(6, 0, options).unwrap();
+        device.set_function(6, 0, true).unwrap();
+
+        let status = device.xlok_status(6).unwrap();
+        assert!(status.options.light);
+        assert!(status.options.reverse);
+        assert_eq!(status.speed, 0);
+
+        match device.set_function(5, 29, true) {
+            Err(Error::InvalidArgument(_)) => (),
+            result => panic!("Unexpected result: {:?}", result)
+        }
+    }
+}
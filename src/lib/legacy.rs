@@ -0,0 +1,242 @@
+/*
+ * File: legacy.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use super::error::*;
+
+pub const MAX_LEGACY_LOK_ADDRESS: u8 = 80;
+pub const MAX_LEGACY_TURNOUT_ADDRESS: u16 = 256;
+pub const MAX_LEGACY_SENSOR_MODULE: u8 = 31;
+
+// The original P50 command bytes, locomotive commands are combined with their speed or functions
+pub(crate) const LOK_FUNCTION: u8 = 0x10;
+pub(crate) const LOK_CHANGE_DIRECTION: u8 = 15;
+pub(crate) const LOK_FUNCTIONS: u8 = 64;
+pub(crate) const TURNOUT_OFF: u8 = 32;
+pub(crate) const TURNOUT_GREEN: u8 = 33;
+pub(crate) const TURNOUT_RED: u8 = 34;
+pub(crate) const GO: u8 = 96;
+pub(crate) const STOP: u8 = 97;
+pub(crate) const S88_MODULES: u8 = 128;
+pub(crate) const S88_MODULE: u8 = 192;
+
+// Contact 1 is the most significant bit of the first byte
+pub(crate) fn contacts_from_bytes(data: [u8; 2]) -> [bool; 16] {
+    let value = u16::from_be_bytes(data);
+    let mut result = [false; 16];
+
+    for (i, contact) in result.iter_mut().enumerate() {
+        *contact = value & (0x8000 >> i) != 0;
+    }
+
+    return result;
+}
+
+pub(crate) fn contacts_to_bytes(contacts: [bool; 16]) -> [u8; 2] {
+    let mut value: u16 = 0;
+
+    for (i, contact) in contacts.iter().enumerate() {
+        if *contact {
+            value |= 0x8000 >> i;
+        }
+    }
+
+    return value.to_be_bytes();
+}
+
+fn check_lok_address(address: u8) -> Result<()> {
+    if address == 0 || address > MAX_LEGACY_LOK_ADDRESS {
+        return Err(Error::InvalidArgument(format!("address {}", address)));
+    }
+
+    return Ok(());
+}
+
+/// Commands of the original 2-byte P50 protocol of the Märklin 6050/6051 interface.
+///
+/// The protocol has no replies except for s88 data, so errors are only reported for invalid parameters. Only
+/// `legacy_command` has to be implemented, all other commands are built on top of it.
+pub trait P50Legacy {
+    fn legacy_command(&mut self, data: &[u8], reply_length: usize) -> Result<Vec<u8>>;
+
+    /// Set the speed from 0 to 14 and the function (light) of a locomotive.
+    fn lok(&mut self, address: u8, speed: u8, function: bool) -> Result<()> {
+        check_lok_address(address)?;
+
+        if speed >= LOK_CHANGE_DIRECTION {
+            return Err(Error::InvalidArgument(format!("speed {}", speed)));
+        }
+
+        let mut data = speed;
+
+        if function {
+            data |= LOK_FUNCTION;
+        }
+
+        self.legacy_command(&[data, address], 0)?;
+
+        return Ok(());
+    }
+
+    fn lok_change_direction(&mut self, address: u8, function: bool) -> Result<()> {
+        check_lok_address(address)?;
+
+        let mut data = LOK_CHANGE_DIRECTION;
+
+        if function {
+            data |= LOK_FUNCTION;
+        }
+
+        self.legacy_command(&[data, address], 0)?;
+
+        return Ok(());
+    }
+
+    fn lok_functions(&mut self, address: u8, functions: [bool; 4]) -> Result<()> {
+        check_lok_address(address)?;
+
+        let mut data = LOK_FUNCTIONS;
+
+        for (i, function) in functions.iter().enumerate() {
+            if *function {
+                data |= 1 << i;
+            }
+        }
+
+        self.legacy_command(&[data, address], 0)?;
+
+        return Ok(());
+    }
+
+    /// Switch a turnout to green (`true`) or red (`false`). The output stays on until `turnout_off` is sent.
+    fn turnout(&mut self, address: u16, state: bool) -> Result<()> {
+        if address == 0 || address > MAX_LEGACY_TURNOUT_ADDRESS {
+            return Err(Error::InvalidArgument(format!("turnout address {}", address)));
+        }
+
+        let data = if state {
+            TURNOUT_GREEN
+        } else {
+            TURNOUT_RED
+        };
+
+        // address 256 is sent as 0
+        self.legacy_command(&[data, address as u8], 0)?;
+
+        return Ok(());
+    }
+
+    fn turnout_off(&mut self) -> Result<()> {
+        self.legacy_command(&[TURNOUT_OFF], 0)?;
+
+        return Ok(());
+    }
+
+    fn go(&mut self) -> Result<()> {
+        self.legacy_command(&[GO], 0)?;
+
+        return Ok(());
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.legacy_command(&[STOP], 0)?;
+
+        return Ok(());
+    }
+
+    fn s88_module(&mut self, module: u8) -> Result<[bool; 16]> {
+        if module == 0 || module > MAX_LEGACY_SENSOR_MODULE {
+            return Err(Error::InvalidArgument(format!("module {}", module)));
+        }
+
+        let data = self.legacy_command(&[S88_MODULE + module], 2)?;
+
+        return Ok(contacts_from_bytes([data[0], data[1]]));
+    }
+
+    /// Read the modules 1 to `count` at once.
+    fn s88_modules(&mut self, count: u8) -> Result<Vec<[bool; 16]>> {
+        if count == 0 || count > MAX_LEGACY_SENSOR_MODULE {
+            return Err(Error::InvalidArgument(format!("module count {}", count)));
+        }
+
+        let data = self.legacy_command(&[S88_MODULES + count], count as usize * 2)?;
+
+        return Ok(data.chunks(2).map(|chunk| contacts_from_bytes([chunk[0], chunk[1]])).collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Device;
+    use crate::protocol::ConnectionMode;
+    use crate::sim::Simulator;
+
+    #[test]
+    fn contacts_start_with_most_significant_bit() {
+        let contacts = contacts_from_bytes([0x80, 0x01]);
+
+        assert!(contacts[0]);
+        assert!(contacts[15]);
+        assert_eq!(contacts.iter().filter(|contact| **contact).count(), 2);
+        assert_eq!(contacts_to_bytes(contacts), [0x80, 0x01]);
+    }
+
+    #[test]
+    fn legacy_commands_change_state() {
+        let mut simulator = Simulator::new();
+        simulator.set_mode(ConnectionMode::Legacy);
+        simulator.set_sensor(2, 0, true);
+        simulator.set_sensor(2, 15, true);
+
+        let mut device = Device::from_transport_unverified(simulator);
+        device.go().unwrap();
+        device.lok(3, 14, true).unwrap();
+        device.lok_change_direction(4, false).unwrap();
+        device.turnout(256, true).unwrap();
+        device.turnout_off().unwrap();
+
+        let mut contacts = [false; 16];
+        contacts[0] = true;
+        contacts[15] = true;
+        assert_eq!(device.s88_module(2).unwrap(), contacts);
+        assert_eq!(device.s88_modules(2).unwrap(), vec![[false; 16], contacts]);
+
+        match device.lok(81, 0, false) {
+            Err(Error::InvalidArgument(_)) => (),
+            result => panic!("Unexpected result {:?}", result)
+        }
+
+        let simulator = device.into_transport();
+        assert!(simulator.power());
+        assert_eq!(simulator.lok_speed(3), 127);
+        assert!(simulator.lok_light(3));
+        assert!(simulator.lok_reverse(4));
+        assert!(simulator.turnout_state(256));
+    }
+}
@@ -31,12 +31,47 @@ mod device;
 mod reply;
 mod protocol;
 mod utils;
+mod transport;
+mod frame;
+mod monitor;
+mod programming;
+mod functions;
+mod ascii;
+mod legacy;
+mod version;
+mod capabilities;
+mod shared;
+mod retry;
+mod outcome;
+mod watchdog;
+mod record;
+mod opcode;
+#[cfg(feature = "async")]
+mod async_device;
+
+pub mod sim;
+pub mod trace;
 
 pub use error::{Error, Result};
 pub use reply::P50XReply;
-pub use device::Device;
-pub use protocol::{P50XBinary, DeviceStatus, XLokOptions, XTurnoutOptions};
+pub use device::{Device, BAUD_RATES};
+pub use protocol::{P50XBinary, ConnectionMode, ConnectionInfo, DeviceStatus, XProtocol, XLokConfig, XLokOptions, XTurnoutOptions, XEventStatus, XLokEvent, XTurnoutEvent, XSensorEvent, XPTEvent};
 pub use utils::bool_arr_to_string;
+pub use transport::{Transport, open_serial};
+pub use monitor::{EventMonitor, Event, LocoEvent, TurnoutEvent, SensorEvent, PowerEvent, ConnectionEvent};
+pub use functions::{LokFunctions, MAX_FUNCTION};
+pub use ascii::{P50XAscii, AsciiReply};
+pub use legacy::{P50Legacy, MAX_LEGACY_LOK_ADDRESS, MAX_LEGACY_TURNOUT_ADDRESS, MAX_LEGACY_SENSOR_MODULE};
+pub use version::VersionInfo;
+pub use capabilities::Capabilities;
+pub use shared::{SharedDevice, Priority};
+pub use retry::{RetryPolicy, TRANSIENT_REPLIES};
+pub use outcome::{Outcome, WARNING_REPLIES};
+pub use watchdog::Watchdog;
+pub use record::{RecordingTransport, ReplayTransport, RecordedData, Direction, parse_session};
+#[cfg(feature = "async")]
+pub use async_device::{AsyncP50XBinary, AsyncDevice};
+pub use programming::{ProgrammingTrack, ProgrammingOnMain, ProgrammingMode, PROGRAMMING_TIMEOUT};
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,433 @@
+/*
+ * File: monitor.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::capabilities::{Capabilities, require};
+use super::error::*;
+use super::protocol::*;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LocoEvent {
+    pub address: u16,
+    pub speed: i8,
+    pub real_speed: i8,
+    pub light: bool,
+    pub functions: [bool; 8]
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TurnoutEvent {
+    pub address: u16,
+    pub state: bool
+}
+
+/// Change of a single s88 contact, contacts are numbered from 0 to 15 within a module.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SensorEvent {
+    pub module: u8,
+    pub contact: u8,
+    pub state: bool
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PowerEvent {
+    On,
+    Off,
+    Halt,
+    ShortCircuit,
+    Overheat
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ConnectionEvent {
+    Connected,
+    Disconnected
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Event {
+    Loco(LocoEvent),
+    Turnout(TurnoutEvent),
+    Sensor(SensorEvent),
+    Power(PowerEvent),
+    Connection(ConnectionEvent),
+    /// Polling failed and the monitor stopped, `stop` returns the error.
+    Stopped
+}
+
+type Callback = Arc<dyn Fn(&Event) + Send + Sync>;
+
+#[derive(Default)]
+pub(crate) struct Subscribers {
+    senders: Vec<Sender<Event>>,
+    callbacks: Vec<Callback>
+}
+
+impl Subscribers {
+    pub(crate) fn subscribe(&mut self) -> Receiver<Event> {
+        let (sender, receiver) = channel();
+        self.senders.push(sender);
+
+        return receiver;
+    }
+
+    pub(crate) fn on_event<F: Fn(&Event) + Send + Sync + 'static>(&mut self, callback: F) {
+        self.callbacks.push(Arc::new(callback));
+    }
+
+    /// Deliver events to all subscribers. Callbacks are called without holding the lock, so they can subscribe as well.
+    pub(crate) fn publish(subscribers: &Mutex<Subscribers>, events: &[Event]) {
+        let callbacks = {
+            let mut subscribers = subscribers.lock().unwrap();
+
+            // drop subscribers which are no longer listening
+            for event in events {
+                subscribers.senders.retain(|sender| sender.send(*event).is_ok());
+            }
+
+            subscribers.callbacks.clone()
+        };
+
+        for event in events {
+            for callback in &callbacks {
+                callback(event);
+            }
+        }
+    }
+}
+
+/// Polls the events of a device in a background thread and delivers them to subscribers.
+///
+/// Subscribe before calling `start` to not miss any events.
+pub struct EventMonitor<D: P50XBinary + Send + 'static> {
+    interval: Duration,
+    running: Arc<AtomicBool>,
+    subscribers: Arc<Mutex<Subscribers>>,
+    device: Option<D>,
+    handle: Option<JoinHandle<Result<D>>>
+}
+
+impl<D: P50XBinary + Send + 'static> EventMonitor<D> {
+    pub fn new(device: D, interval: Duration) -> EventMonitor<D> {
+        return EventMonitor {
+            interval,
+            running: Arc::new(AtomicBool::new(false)),
+            subscribers: Arc::new(Mutex::new(Subscribers::default())),
+            device: Some(device),
+            handle: None
+        };
+    }
+
+    /// Start polling events in the background. Fails if the device does not support events.
+    ///
+    /// Power changes are reported relative to the power state when `start` returns. Polls failing with a retryable
+    /// error are skipped, any other error stops the monitor with `Event::Stopped`.
+    pub fn start(&mut self) -> Result<()> {
+        let mut device = match self.device.take() {
+            Some(device) => device,
+            None => return Ok(())
+        };
+
+        let mut poller = Poller::default();
+
+        if let Err(err) = require(&device, Capabilities::EVENTS).and_then(|_| poller.read_status(&mut device)) {
+            self.device = Some(device);
+
+            return Err(err);
+        }
+
+        self.running.store(true, Ordering::SeqCst);
+
+        let interval = self.interval;
+        let running = self.running.clone();
+        let subscribers = self.subscribers.clone();
+
+        self.handle = Some(thread::spawn(move || {
+            while running.load(Ordering::SeqCst) {
+                let events = match poller.poll(&mut device) {
+                    Ok(events) => events,
+                    // the device might answer again at the next poll, e.g. once the watchdog reconnected it
+                    Err(ref err) if err.is_retryable() => Vec::new(),
+                    Err(err) => {
+                        Subscribers::publish(&subscribers, &[Event::Stopped]);
+
+                        return Err(err);
+                    }
+                };
+
+                if events.is_empty() == false {
+                    Subscribers::publish(&subscribers, &events);
+                }
+
+                thread::park_timeout(interval);
+            }
+
+            return Ok(device);
+        }));
+
+        return Ok(());
+    }
+
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.subscribers.lock().unwrap().subscribe()
+    }
+
+    pub fn on_event<F: Fn(&Event) + Send + Sync + 'static>(&self, callback: F) {
+        self.subscribers.lock().unwrap().on_event(callback);
+    }
+
+    pub fn is_running(&self) -> bool {
+        match self.handle {
+            Some(ref handle) => handle.is_finished() == false,
+            None => false
+        }
+    }
+
+    /// Stop monitoring and hand back the device. Fails with the error which ended the monitoring early, if any.
+    pub fn stop(mut self) -> Result<D> {
+        if let Some(device) = self.device.take() {
+            return Ok(device);
+        }
+
+        return self.join().unwrap();
+    }
+
+    fn join(&mut self) -> Option<Result<D>> {
+        let handle = self.handle.take()?;
+
+        self.running.store(false, Ordering::SeqCst);
+        handle.thread().unpark();
+
+        match handle.join() {
+            Ok(result) => Some(result),
+            Err(_) => Some(Err(Error::Other))
+        }
+    }
+}
+
+impl<D: P50XBinary + Send + 'static> Drop for EventMonitor<D> {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+#[derive(Default)]
+struct Poller {
+    status: Option<DeviceStatus>,
+    sensors: HashMap<u8, [bool; 16]>
+}
+
+impl Poller {
+    // Power state is not part of the event flags so it has to be compared to the last known status
+    fn read_status<D: P50XBinary>(&mut self, device: &mut D) -> Result<()> {
+        self.status = Some(device.xstatus()?);
+
+        return Ok(());
+    }
+
+    fn poll<D: P50XBinary>(&mut self, device: &mut D) -> Result<Vec<Event>> {
+        let mut events = Vec::new();
+
+        let flags = device.xevent()?;
+
+        if flags.power_off || flags.status {
+            let status = device.xstatus()?;
+
+            if let Some(previous) = self.status.replace(status) {
+                if status.power != previous.power {
+                    events.push(Event::Power(if status.power { PowerEvent::On } else { PowerEvent::Off }));
+                }
+
+                if status.halt && previous.halt == false {
+                    events.push(Event::Power(PowerEvent::Halt));
+                }
+            }
+        }
+
+        if flags.external_short || flags.lokmaus_short || flags.internal_short || flags.booster_short || flags.programming_short {
+            events.push(Event::Power(PowerEvent::ShortCircuit));
+        }
+
+        if flags.overheat {
+            events.push(Event::Power(PowerEvent::Overheat));
+        }
+
+        if flags.lok {
+            for event in device.xevt_lok()? {
+                events.push(Event::Loco(LocoEvent {
+                    address: event.address,
+                    speed: event.speed,
+                    real_speed: event.real_speed,
+                    light: event.light,
+                    functions: event.functions
+                }));
+            }
+        }
+
+        if flags.turnout {
+            for event in device.xevt_turnout()? {
+                events.push(Event::Turnout(TurnoutEvent {
+                    address: event.address,
+                    state: event.state
+                }));
+            }
+        }
+
+        if flags.sensor {
+            for event in device.xevt_sensor()? {
+                let previous = self.sensors.insert(event.module, event.contacts).unwrap_or([false; 16]);
+
+                for (i, (state, previous_state)) in event.contacts.iter().zip(previous.iter()).enumerate() {
+                    if state != previous_state {
+                        events.push(Event::Sensor(SensorEvent {
+                            module: event.module,
+                            contact: i as u8,
+                            state: *state
+                        }));
+                    }
+                }
+            }
+        }
+
+        return Ok(events);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Device;
+    use crate::record::{parse_session, ReplayTransport};
+    use crate::sim::Simulator;
+
+    #[test]
+    fn events_are_delivered_to_subscribers() {
+        let mut simulator = Simulator::new();
+        simulator.set_power(true);
+        simulator.drive_lok(42, 20);
+        simulator.switch_turnout(7, true);
+        simulator.set_sensor(1, 3, true);
+
+        let device = Device::from_transport(simulator).unwrap();
+        let mut monitor = EventMonitor::new(device, Duration::from_millis(10));
+
+        let receiver = monitor.subscribe();
+        let callback_events = Arc::new(Mutex::new(Vec::new()));
+        let callback_target = callback_events.clone();
+        monitor.on_event(move |event| callback_target.lock().unwrap().push(*event));
+        monitor.start().unwrap();
+        assert!(monitor.is_running());
+
+        let mut events = Vec::new();
+        while events.len() < 3 {
+            events.push(receiver.recv_timeout(Duration::from_secs(1)).unwrap());
+        }
+
+        assert!(events.contains(&Event::Turnout(TurnoutEvent { address: 7, state: true })));
+        assert!(events.contains(&Event::Sensor(SensorEvent { module: 1, contact: 3, state: true })));
+        assert!(events.iter().any(|event| match event {
+            Event::Loco(loco) => loco.address == 42 && loco.speed == 20,
+            _ => false
+        }));
+
+        let device = monitor.stop().unwrap();
+        assert_eq!(callback_events.lock().unwrap().len(), 3);
+        assert_eq!(device.into_transport().lok_speed(42), 20);
+    }
+
+    #[test]
+    fn power_changes_are_reported() {
+        let device = Device::from_transport(Simulator::new()).unwrap();
+        let mut shared = crate::SharedDevice::new(device);
+        let mut monitor = EventMonitor::new(shared.clone(), Duration::from_millis(10));
+
+        let receiver = monitor.subscribe();
+        monitor.start().unwrap();
+
+        shared.xpower_on().unwrap();
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(1)), Ok(Event::Power(PowerEvent::On)));
+
+        shared.xpower_off().unwrap();
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(1)), Ok(Event::Power(PowerEvent::Off)));
+
+        monitor.stop().unwrap();
+    }
+
+    #[test]
+    fn failed_poll_stops_monitoring() {
+        // the session ends after the power state is read
+        let session = parse_session("0.000 > 58 A2\n0.001 < 00").unwrap();
+
+        let mut device = Device::from_transport_unverified(ReplayTransport::new(session));
+        device.set_capabilities(Some(Capabilities::EVENTS));
+
+        let mut monitor = EventMonitor::new(device, Duration::from_millis(10));
+        let receiver = monitor.subscribe();
+        monitor.start().unwrap();
+
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(1)), Ok(Event::Stopped));
+        assert!(monitor.stop().is_err());
+    }
+
+    #[test]
+    fn callbacks_can_subscribe() {
+        let mut simulator = Simulator::new();
+        simulator.switch_turnout(7, true);
+
+        let device = Device::from_transport(simulator).unwrap();
+        let mut monitor = EventMonitor::new(device, Duration::from_millis(10));
+
+        let (sender, receiver) = channel();
+        let subscribers = monitor.subscribers.clone();
+        monitor.on_event(move |_| {
+            sender.send(subscribers.lock().unwrap().subscribe()).unwrap();
+        });
+        monitor.start().unwrap();
+
+        assert!(receiver.recv_timeout(Duration::from_secs(1)).is_ok());
+    }
+
+    #[test]
+    fn start_fails_without_event_support() {
+        let mut device = Device::from_transport(Simulator::new()).unwrap();
+        device.set_capabilities(Some(Capabilities::empty()));
+
+        let mut monitor = EventMonitor::new(device, Duration::from_millis(10));
+        assert!(monitor.start().is_err());
+        assert!(monitor.is_running() == false);
+
+        // the device is handed back untouched
+        assert!(monitor.stop().is_ok());
+    }
+}
@@ -0,0 +1,134 @@
+/*
+ * File: opcode.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+// Opcodes of the P50Xb commands, sent after the extended character
+
+pub(crate) const XLOK: u8 = 0x80;
+pub(crate) const XLOK_DISPATCH: u8 = 0x83;
+pub(crate) const XLOK_STATUS: u8 = 0x84;
+pub(crate) const XLOK_CONFIG: u8 = 0x85;
+pub(crate) const XLOK_SET_CONFIG: u8 = 0x86;
+pub(crate) const XFUNC: u8 = 0x88;
+pub(crate) const XFUNCX: u8 = 0x89;
+pub(crate) const XFUNC34: u8 = 0x8A;
+pub(crate) const XFUNC_STATUS: u8 = 0x8C;
+pub(crate) const XFUNCX_STATUS: u8 = 0x8D;
+pub(crate) const XFUNC34_STATUS: u8 = 0x8E;
+pub(crate) const XTRNT: u8 = 0x90;
+pub(crate) const XTRNT_FREE: u8 = 0x93;
+pub(crate) const XTRNT_STATUS: u8 = 0x94;
+pub(crate) const XTRNT_GROUP: u8 = 0x95;
+pub(crate) const XSENSOR: u8 = 0x98;
+pub(crate) const XSENS_OFF: u8 = 0x99;
+pub(crate) const X88P_GET: u8 = 0x9C;
+pub(crate) const X88P_SET: u8 = 0x9D;
+pub(crate) const XS88_TIM: u8 = 0x9E;
+pub(crate) const XS88_CNT: u8 = 0x9F;
+pub(crate) const XVER: u8 = 0xA0;
+pub(crate) const XP50XCH: u8 = 0xA1;
+pub(crate) const XSTATUS: u8 = 0xA2;
+pub(crate) const XSO_SET: u8 = 0xA3;
+pub(crate) const XSO_GET: u8 = 0xA4;
+pub(crate) const XHALT: u8 = 0xA5;
+pub(crate) const XPWR_OFF: u8 = 0xA6;
+pub(crate) const XPWR_ON: u8 = 0xA7;
+pub(crate) const XNOP: u8 = 0xC4;
+pub(crate) const XEVENT: u8 = 0xC8;
+pub(crate) const XEVT_LOK: u8 = 0xC9;
+pub(crate) const XEVT_TRN: u8 = 0xCA;
+pub(crate) const XEVT_SEN: u8 = 0xCB;
+pub(crate) const XEVT_PT: u8 = 0xCE;
+pub(crate) const XPOM_RD: u8 = 0xDD;
+pub(crate) const XPOM_WR: u8 = 0xDE;
+pub(crate) const XPOM_WR_BIT: u8 = 0xDF;
+pub(crate) const XPT_TERM: u8 = 0xE4;
+pub(crate) const XPT_MOT_WR_REG: u8 = 0xE8;
+pub(crate) const XPT_DCC_RD_REG: u8 = 0xEA;
+pub(crate) const XPT_DCC_WR_REG: u8 = 0xEB;
+pub(crate) const XPT_DCC_RD_PG: u8 = 0xEC;
+pub(crate) const XPT_DCC_WR_PG: u8 = 0xED;
+pub(crate) const XPT_DCC_RD_PHY: u8 = 0xEE;
+pub(crate) const XPT_DCC_WR_PHY: u8 = 0xEF;
+pub(crate) const XPT_DCC_RD_BYTE: u8 = 0xF0;
+pub(crate) const XPT_DCC_RD_BIT: u8 = 0xF1;
+pub(crate) const XPT_DCC_WR_BIT: u8 = 0xF2;
+pub(crate) const XPT_DCC_WR_BYTE: u8 = 0xF3;
+
+// Names of the commands as used in the protocol documentation
+pub(crate) const NAMES: [(u8, &str); 50] = [
+    (XLOK, "XLok"),
+    (XLOK_DISPATCH, "XLokDispatch"),
+    (XLOK_STATUS, "XLokStatus"),
+    (XLOK_CONFIG, "XLokConfig"),
+    (XLOK_SET_CONFIG, "XLokSetConfig"),
+    (XFUNC, "XFunc"),
+    (XFUNCX, "XFuncX"),
+    (XFUNC34, "XFunc34"),
+    (XFUNC_STATUS, "XFuncStatus"),
+    (XFUNCX_STATUS, "XFuncXStatus"),
+    (XFUNC34_STATUS, "XFunc34Status"),
+    (XTRNT, "XTrnt"),
+    (XTRNT_FREE, "XTrntFree"),
+    (XTRNT_STATUS, "XTrntStatus"),
+    (XTRNT_GROUP, "XTrntGroup"),
+    (XSENSOR, "XSensor"),
+    (XSENS_OFF, "XSensOff"),
+    (X88P_GET, "X88PGet"),
+    (X88P_SET, "X88PSet"),
+    (XS88_TIM, "XS88Tim"),
+    (XS88_CNT, "XS88Cnt"),
+    (XVER, "XVer"),
+    (XP50XCH, "XP50XCh"),
+    (XSTATUS, "XStatus"),
+    (XSO_SET, "XSOSet"),
+    (XSO_GET, "XSOGet"),
+    (XHALT, "XHalt"),
+    (XPWR_OFF, "XPwrOff"),
+    (XPWR_ON, "XPwrOn"),
+    (XNOP, "XNOP"),
+    (XEVENT, "XEvent"),
+    (XEVT_LOK, "XEvtLok"),
+    (XEVT_TRN, "XEvtTrn"),
+    (XEVT_SEN, "XEvtSen"),
+    (XEVT_PT, "XEvtPT"),
+    (XPOM_RD, "XPOMRd"),
+    (XPOM_WR, "XPOMWr"),
+    (XPOM_WR_BIT, "XPOMWrBit"),
+    (XPT_TERM, "XPT_Term"),
+    (XPT_MOT_WR_REG, "XPT_MotWrReg"),
+    (XPT_DCC_RD_REG, "XPT_DCCRdReg"),
+    (XPT_DCC_WR_REG, "XPT_DCCWrReg"),
+    (XPT_DCC_RD_PG, "XPT_DCCRdPg"),
+    (XPT_DCC_WR_PG, "XPT_DCCWrPg"),
+    (XPT_DCC_RD_PHY, "XPT_DCCRdPhy"),
+    (XPT_DCC_WR_PHY, "XPT_DCCWrPhy"),
+    (XPT_DCC_RD_BYTE, "XPT_DCCRdByte"),
+    (XPT_DCC_RD_BIT, "XPT_DCCRdBit"),
+    (XPT_DCC_WR_BIT, "XPT_DCCWrBit"),
+    (XPT_DCC_WR_BYTE, "XPT_DCCWrByte")
+];
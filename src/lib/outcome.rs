@@ -0,0 +1,97 @@
+/*
+ * File: outcome.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use super::reply::P50XReply;
+
+/// Replies which mean a command was accepted, but the device can not execute it right now.
+pub const WARNING_REPLIES: [P50XReply; 3] = [
+    P50XReply::LokHalt,
+    P50XReply::LokPowerOff,
+    P50XReply::LowTurnoutCommandStackSpace
+];
+
+/// Result of an accepted command together with the warnings the device replied with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome<T> {
+    pub value: T,
+    pub warnings: Vec<P50XReply>
+}
+
+impl<T> Outcome<T> {
+    pub fn new(value: T) -> Outcome<T> {
+        return Outcome {
+            value,
+            warnings: Vec::new()
+        };
+    }
+
+    pub(crate) fn from_reply(value: T, reply: P50XReply) -> Outcome<T> {
+        let mut outcome = Outcome::new(value);
+
+        if reply != P50XReply::Ok {
+            outcome.warnings.push(reply);
+        }
+
+        return outcome;
+    }
+
+    pub fn has_warnings(&self) -> bool {
+        self.warnings.is_empty() == false
+    }
+
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Outcome<U> {
+        return Outcome {
+            value: f(self.value),
+            warnings: self.warnings
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Device, P50XBinary, XLokOptions};
+    use crate::sim::Simulator;
+
+    #[test]
+    fn lok_command_reports_power_off() {
+        let mut device = Device::from_transport(Simulator::new()).unwrap();
+
+        // the command is still accepted
+        let outcome = device.xlok(3, 10, XLokOptions::default()).unwrap();
+        assert_eq!(outcome.warnings, vec![P50XReply::LokPowerOff]);
+        assert_eq!(device.xlok_status(3).unwrap().speed, 10);
+
+        device.xpower_on().unwrap();
+        assert!(device.xlok(3, 20, XLokOptions::default()).unwrap().has_warnings() == false);
+    }
+}
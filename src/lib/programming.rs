@@ -0,0 +1,295 @@
+/*
+ * File: programming.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::capabilities::{Capabilities, require};
+use super::error::*;
+use super::protocol::P50XBinary;
+use super::reply::P50XReply;
+
+pub const PROGRAMMING_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+const MAX_CV: u16 = 1024;
+const MAX_DCC_REGISTER: u16 = 8;
+const MAX_MOTOROLA_REGISTER: u16 = 80;
+const MAX_DCC_ADDRESS: u16 = 10239;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ProgrammingMode {
+    Direct,
+    Page,
+    Register,
+    PhysicalRegister,
+    Motorola
+}
+
+/// Programming track operations which start a programming task and wait for its result.
+///
+/// CV numbers start at 1, registers are used as CV number in register and motorola mode.
+pub trait ProgrammingTrack: P50XBinary {
+    fn pt_wait(&mut self, timeout: Duration) -> Result<Option<u8>> {
+        let start = Instant::now();
+
+        loop {
+            if let Some(event) = self.xevt_pt()? {
+                if event.status != P50XReply::Ok {
+                    return Err(Error::Reply(event.status));
+                }
+
+                return Ok(event.value);
+            }
+
+            if start.elapsed() >= timeout {
+                self.xpt_term()?;
+
+                return Err(Error::Reply(P50XReply::ProgrammingTimeout));
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn pt_read(&mut self, mode: ProgrammingMode, cv: u16) -> Result<u8> {
+        require(self, Capabilities::PROGRAMMING_TRACK)?;
+        check_cv(mode, cv)?;
+
+        match mode {
+            ProgrammingMode::Direct => self.xpt_dcc_read_byte(cv)?,
+            ProgrammingMode::Page => self.xpt_dcc_read_page(cv)?,
+            ProgrammingMode::Register => self.xpt_dcc_read_register(cv as u8)?,
+            ProgrammingMode::PhysicalRegister => self.xpt_dcc_read_physical_register(cv as u8)?,
+            // motorola decoders can not be read
+            ProgrammingMode::Motorola => return Err(Error::Unsupported("reading motorola decoders".to_string()))
+        };
+
+        match self.pt_wait(PROGRAMMING_TIMEOUT)? {
+            Some(value) => Ok(value),
+            None => Err(Error::UnknownResponse("Missing programming track value".to_string()))
+        }
+    }
+
+    fn pt_write(&mut self, mode: ProgrammingMode, cv: u16, value: u8) -> Result<()> {
+        require(self, Capabilities::PROGRAMMING_TRACK)?;
+        check_cv(mode, cv)?;
+
+        match mode {
+            ProgrammingMode::Direct => self.xpt_dcc_write_byte(cv, value)?,
+            ProgrammingMode::Page => self.xpt_dcc_write_page(cv, value)?,
+            ProgrammingMode::Register => self.xpt_dcc_write_register(cv as u8, value)?,
+            ProgrammingMode::PhysicalRegister => self.xpt_dcc_write_physical_register(cv as u8, value)?,
+            ProgrammingMode::Motorola => self.xpt_motorola_write_register(cv as u8, value)?
+        };
+
+        self.pt_wait(PROGRAMMING_TIMEOUT)?;
+
+        return Ok(());
+    }
+
+    fn pt_read_bit(&mut self, cv: u16, bit: u8) -> Result<bool> {
+        require(self, Capabilities::PROGRAMMING_TRACK)?;
+        check_cv(ProgrammingMode::Direct, cv)?;
+        check_bit(bit)?;
+
+        self.xpt_dcc_read_bit(cv, bit)?;
+
+        match self.pt_wait(PROGRAMMING_TIMEOUT)? {
+            Some(value) => Ok(value != 0),
+            None => Err(Error::UnknownResponse("Missing programming track value".to_string()))
+        }
+    }
+
+    fn pt_write_bit(&mut self, cv: u16, bit: u8, value: bool) -> Result<()> {
+        require(self, Capabilities::PROGRAMMING_TRACK)?;
+        check_cv(ProgrammingMode::Direct, cv)?;
+        check_bit(bit)?;
+
+        self.xpt_dcc_write_bit(cv, bit, value)?;
+        self.pt_wait(PROGRAMMING_TIMEOUT)?;
+
+        return Ok(());
+    }
+}
+
+impl<D: P50XBinary + ?Sized> ProgrammingTrack for D {}
+
+/// Programming on the main track of dcc decoders addressed by their locomotive address.
+///
+/// Reading requires a command station with RailCom feedback, otherwise it fails with `P50XReply::BadCommand`.
+pub trait ProgrammingOnMain: P50XBinary {
+    fn pom_read(&mut self, address: u16, cv: u16) -> Result<u8> {
+        require(self, Capabilities::PROGRAMMING_ON_MAIN)?;
+        check_address(address)?;
+        check_cv(ProgrammingMode::Direct, cv)?;
+
+        self.xpom_read(address, cv)
+    }
+
+    fn pom_write(&mut self, address: u16, cv: u16, value: u8) -> Result<()> {
+        require(self, Capabilities::PROGRAMMING_ON_MAIN)?;
+        check_address(address)?;
+        check_cv(ProgrammingMode::Direct, cv)?;
+
+        self.xpom_write(address, cv, value)
+    }
+
+    fn pom_write_bit(&mut self, address: u16, cv: u16, bit: u8, value: bool) -> Result<()> {
+        require(self, Capabilities::PROGRAMMING_ON_MAIN)?;
+        check_address(address)?;
+        check_cv(ProgrammingMode::Direct, cv)?;
+        check_bit(bit)?;
+
+        self.xpom_write_bit(address, cv, bit, value)
+    }
+}
+
+impl<D: P50XBinary + ?Sized> ProgrammingOnMain for D {}
+
+fn check_address(address: u16) -> Result<()> {
+    if address == 0 || address > MAX_DCC_ADDRESS {
+        return Err(Error::InvalidArgument(format!("address {}", address)));
+    }
+
+    return Ok(());
+}
+
+fn check_cv(mode: ProgrammingMode, cv: u16) -> Result<()> {
+    let max = match mode {
+        ProgrammingMode::Direct | ProgrammingMode::Page => MAX_CV,
+        ProgrammingMode::Register | ProgrammingMode::PhysicalRegister => MAX_DCC_REGISTER,
+        ProgrammingMode::Motorola => MAX_MOTOROLA_REGISTER
+    };
+
+    if cv == 0 || cv > max {
+        return Err(Error::InvalidArgument(format!("cv {}", cv)));
+    }
+
+    return Ok(());
+}
+
+fn check_bit(bit: u8) -> Result<()> {
+    if bit > 7 {
+        return Err(Error::InvalidArgument(format!("bit {}", bit)));
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Device;
+    use crate::sim::Simulator;
+
+    #[test]
+    fn read_and_write_cvs() {
+        let mut device = Device::from_transport(Simulator::new()).unwrap();
+
+        assert_eq!(device.pt_read(ProgrammingMode::Direct, 1).unwrap(), 3);
+
+        device.pt_write(ProgrammingMode::Direct, 1, 42).unwrap();
+        assert_eq!(device.pt_read(ProgrammingMode::Page, 1).unwrap(), 42);
+
+        device.pt_write_bit(29, 0, true).unwrap();
+        assert!(device.pt_read_bit(29, 0).unwrap());
+        assert_eq!(device.pt_read(ProgrammingMode::Register, 5).unwrap(), 7);
+    }
+
+    #[test]
+    fn unsupported_commands_are_not_sent() {
+        let mut device = Device::from_transport(Simulator::new()).unwrap();
+        device.set_capabilities(Some(Capabilities::all()));
+        device.pt_write(ProgrammingMode::Direct, 1, 5).unwrap();
+
+        let mut capabilities = Capabilities::all();
+        capabilities.remove(Capabilities::PROGRAMMING_TRACK);
+        device.set_capabilities(Some(capabilities));
+
+        match device.pt_read(ProgrammingMode::Direct, 1) {
+            Err(Error::Unsupported(_)) => (),
+            result => panic!("Unexpected result {:?}", result)
+        }
+
+        assert_eq!(device.into_transport().programming_cv(1), 5);
+    }
+
+    #[test]
+    fn programming_errors_are_reported() {
+        let mut simulator = Simulator::new();
+        simulator.set_programming_decoder(false);
+
+        let mut device = Device::from_transport(simulator).unwrap();
+
+        match device.pt_read(ProgrammingMode::Direct, 1) {
+            Err(ref err) if err.reply() == Some(P50XReply::ProgrammingNoAcknowledge) => (),
+            result => panic!("Unexpected result: {:?}", result)
+        }
+
+        match device.pt_read(ProgrammingMode::Direct, 1025) {
+            Err(Error::InvalidArgument(_)) => (),
+            result => panic!("Unexpected result: {:?}", result)
+        }
+
+        match device.pt_read(ProgrammingMode::Motorola, 1) {
+            Err(Error::Unsupported(_)) => (),
+            result => panic!("Unexpected result: {:?}", result)
+        }
+    }
+
+    #[test]
+    fn programming_on_main() {
+        let mut simulator = Simulator::new();
+        simulator.set_power(true);
+
+        let mut device = Device::from_transport(simulator).unwrap();
+
+        device.pom_write(1234, 3, 20).unwrap();
+        device.pom_write_bit(1234, 29, 5, true).unwrap();
+
+        // reading is only possible with railcom feedback
+        match device.pom_read(1234, 3) {
+            Err(ref err) if err.reply() == Some(P50XReply::BadCommand) => (),
+            result => panic!("Unexpected result: {:?}", result)
+        }
+
+        match device.pom_write(1234, 0, 20) {
+            Err(Error::InvalidArgument(_)) => (),
+            result => panic!("Unexpected result: {:?}", result)
+        }
+
+        let mut simulator = device.into_transport();
+        assert_eq!(simulator.lok_cv(1234, 3), 20);
+        assert_eq!(simulator.lok_cv(1234, 29), 0x20);
+
+        simulator.set_railcom(true);
+        let mut device = Device::from_transport(simulator).unwrap();
+        assert_eq!(device.pom_read(1234, 3).unwrap(), 20);
+    }
+}
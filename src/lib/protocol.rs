@@ -29,8 +29,12 @@
 use std::convert::From;
 use std::fmt;
 
+use super::capabilities::Capabilities;
 use super::error::Result;
+use super::outcome::Outcome;
+use super::reply::P50XReply;
 use super::utils::bool_arr_to_string;
+use super::version::VersionInfo;
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 #[repr(u8)]
@@ -52,6 +56,44 @@ impl From<u8> for XProtocol {
     }
 }
 
+impl XProtocol {
+    pub fn supports_speed_steps(&self, speed_steps: u8) -> bool {
+        let valid: &[u8] = match self {
+            XProtocol::Motorola => &[14, 27, 28],
+            XProtocol::Selectrix => &[31],
+            XProtocol::DCC => &[14, 27, 28, 128],
+            XProtocol::FMZ => &[15]
+        };
+
+        return valid.contains(&speed_steps);
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ConnectionMode {
+    Binary,
+    Ascii,
+    Legacy
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ConnectionInfo {
+    pub mode: ConnectionMode,
+    pub extended_character: Option<u8>,
+    pub switched_to_binary: bool
+}
+
+impl fmt::Display for ConnectionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let extended_character = match self.extended_character {
+            Some(value) => format!("0x{:02X}", value),
+            None => "Unknown".to_string()
+        };
+
+        write!(f, "Mode: {:?}\nExtended character: {}\nSwitched to binary: {}", self.mode, extended_character, self.switched_to_binary)
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct DeviceStatus {
     pub stop_pressed: bool,
@@ -82,6 +124,8 @@ pub struct XLokOptions {
     pub emergency_stop: bool,
     pub force: bool,
     pub light: bool,
+    /// Drive in reverse, which is also kept by stopped locomotives. Negative speeds always drive in reverse.
+    pub reverse: bool,
     pub functions: Option<[bool; 4]>
 }
 
@@ -94,12 +138,13 @@ pub struct XLokStatus {
 
 impl fmt::Display for XLokStatus {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Speed: {}\nReal speed: {}\nEmergency stop: {}\nForce: {}\nLight: {}\nFunctions: {}",
+        write!(f, "Speed: {}\nReal speed: {}\nEmergency stop: {}\nForce: {}\nLight: {}\nReverse: {}\nFunctions: {}",
             self.speed,
             self.real_speed,
             self.options.emergency_stop,
             self.options.force,
             self.options.light,
+            self.options.reverse,
             bool_arr_to_string(&self.options.functions.unwrap())
         )
     }
@@ -148,13 +193,113 @@ impl fmt::Display for XTurnoutStatus {
     }
 }
 
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct XEventStatus {
+    pub lok: bool,
+    pub sensor: bool,
+    pub power_off: bool,
+    pub turnout: bool,
+    pub external_short: bool,
+    pub lokmaus_short: bool,
+    pub internal_short: bool,
+    pub booster_short: bool,
+    pub programming_short: bool,
+    pub overheat: bool,
+    pub status: bool,
+    pub programming: bool,
+    pub external_voltage: bool
+}
+
+impl XEventStatus {
+    pub fn any(&self) -> bool {
+        *self != XEventStatus::default()
+    }
+}
+
+impl fmt::Display for XEventStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Locomotive: {}\nSensor: {}\nPower off: {}\nTurnout: {}\nShort circuit: {}\nProgramming short circuit: {}\nOverheat: {}\nStatus: {}\nProgramming: {}\nExternal voltage: {}",
+            self.lok,
+            self.sensor,
+            self.power_off,
+            self.turnout,
+            self.external_short || self.lokmaus_short || self.internal_short || self.booster_short,
+            self.programming_short,
+            self.overheat,
+            self.status,
+            self.programming,
+            self.external_voltage
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct XLokEvent {
+    pub address: u16,
+    pub speed: i8,
+    pub real_speed: i8,
+    pub light: bool,
+    pub functions: [bool; 8]
+}
+
+impl fmt::Display for XLokEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Address: {}\nSpeed: {}\nReal speed: {}\nLight: {}\nFunctions: {}",
+            self.address,
+            self.speed,
+            self.real_speed,
+            self.light,
+            bool_arr_to_string(&self.functions)
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct XTurnoutEvent {
+    pub address: u16,
+    pub state: bool
+}
+
+impl fmt::Display for XTurnoutEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Address: {}\nState: {}", self.address, self.state)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct XSensorEvent {
+    pub module: u8,
+    pub contacts: [bool; 16]
+}
+
+impl fmt::Display for XSensorEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Module: {}\nContacts: {}", self.module, bool_arr_to_string(&self.contacts))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct XPTEvent {
+    pub status: P50XReply,
+    pub value: Option<u8>
+}
+
+impl fmt::Display for XPTEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value {
+            Some(value) => write!(f, "Status: {}\nValue: {}", self.status, value),
+            None => write!(f, "Status: {}", self.status)
+        }
+    }
+}
+
 pub trait P50XBinary {
     fn xpower_off(&mut self) -> Result<()>;
     fn xpower_on(&mut self) -> Result<()>;
     fn xhalt(&mut self) -> Result<()>;
     fn xso_set(&mut self, special_option: u16, value: u8) -> Result<()>;
     fn xso_get(&mut self, special_option: u16) -> Result<u8>;
-    fn xversion(&mut self) -> Result<Vec<u8>>;
+    fn xversion(&mut self) -> Result<VersionInfo>;
     fn xp50xch(&mut self, extended_character: u8) -> Result<()>;
     fn xstatus(&mut self) -> Result<DeviceStatus>;
     fn xnop(&mut self) -> Result<()>;
@@ -166,17 +311,48 @@ pub trait P50XBinary {
     fn xs88_timer(&mut self, timer: u8, reset: bool) -> Result<u16>;
     fn xs88_count(&mut self, timer: u8, reset: bool) -> Result<u16>;
 
-    fn xlok(&mut self, address: u16, speed: i8, options: XLokOptions) -> Result<()>;
+    fn xlok(&mut self, address: u16, speed: i8, options: XLokOptions) -> Result<Outcome<()>>;
     fn xlok_status(&mut self, address: u16) -> Result<XLokStatus>;
     fn xlok_config(&mut self, address: u16) -> Result<XLokConfig>;
+    fn xlok_set_config(&mut self, address: u16, config: XLokConfig) -> Result<()>;
     fn xlok_dispatch(&mut self, address: u16) -> Result<Option<u8>>;
-    fn xfunc(&mut self, address: u16, functions: [bool; 8]) -> Result<()>;
+    fn xfunc(&mut self, address: u16, functions: [bool; 8]) -> Result<Outcome<()>>;
     fn xfunc_status(&mut self, address: u16) -> Result<[bool; 8]>;
-    fn xfuncx(&mut self, address: u16, functions: [bool; 8]) -> Result<()>;
+    fn xfuncx(&mut self, address: u16, functions: [bool; 8]) -> Result<Outcome<()>>;
     fn xfuncx_status(&mut self, address: u16) -> Result<[bool; 8]>;
+    fn xfunc34(&mut self, address: u16, functions: [bool; 16]) -> Result<Outcome<()>>;
+    fn xfunc34_status(&mut self, address: u16) -> Result<[bool; 16]>;
 
-    fn xturnout(&mut self, address: u16, state: bool, options: XTurnoutOptions) -> Result<()>;
+    fn xturnout(&mut self, address: u16, state: bool, options: XTurnoutOptions) -> Result<Outcome<()>>;
     fn xturnout_free(&mut self) -> Result<()>;
     fn xturnout_status(&mut self, address: u16) -> Result<XTurnoutStatus>;
     fn xturnout_group(&mut self, group_address: u8) -> Result<[(bool, bool); 8]>;
+
+    fn xevent(&mut self) -> Result<XEventStatus>;
+    fn xevt_lok(&mut self) -> Result<Vec<XLokEvent>>;
+    fn xevt_turnout(&mut self) -> Result<Vec<XTurnoutEvent>>;
+    fn xevt_sensor(&mut self) -> Result<Vec<XSensorEvent>>;
+
+    fn xevt_pt(&mut self) -> Result<Option<XPTEvent>>;
+    fn xpt_term(&mut self) -> Result<()>;
+    fn xpt_motorola_write_register(&mut self, register: u8, value: u8) -> Result<()>;
+    fn xpt_dcc_read_register(&mut self, register: u8) -> Result<()>;
+    fn xpt_dcc_write_register(&mut self, register: u8, value: u8) -> Result<()>;
+    fn xpt_dcc_read_page(&mut self, cv: u16) -> Result<()>;
+    fn xpt_dcc_write_page(&mut self, cv: u16, value: u8) -> Result<()>;
+    fn xpt_dcc_read_physical_register(&mut self, register: u8) -> Result<()>;
+    fn xpt_dcc_write_physical_register(&mut self, register: u8, value: u8) -> Result<()>;
+    fn xpt_dcc_read_byte(&mut self, cv: u16) -> Result<()>;
+    fn xpt_dcc_read_bit(&mut self, cv: u16, bit: u8) -> Result<()>;
+    fn xpt_dcc_write_bit(&mut self, cv: u16, bit: u8, value: bool) -> Result<()>;
+    fn xpt_dcc_write_byte(&mut self, cv: u16, value: u8) -> Result<()>;
+
+    fn xpom_read(&mut self, address: u16, cv: u16) -> Result<u8>;
+    fn xpom_write(&mut self, address: u16, cv: u16, value: u8) -> Result<()>;
+    fn xpom_write_bit(&mut self, address: u16, cv: u16, bit: u8, value: bool) -> Result<()>;
+
+    /// Commands known to be supported by the device, `None` if they were not determined.
+    fn capabilities(&self) -> Option<Capabilities> {
+        return None;
+    }
 }
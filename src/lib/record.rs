@@ -0,0 +1,319 @@
+/*
+ * File: record.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use super::error::*;
+use super::trace::hex;
+use super::transport::Transport;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Direction {
+    Sent,
+    Received
+}
+
+/// Data sent to or received from a device at a time since the start of a session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedData {
+    pub time: Duration,
+    pub direction: Direction,
+    pub data: Vec<u8>
+}
+
+impl RecordedData {
+    /// Parse a single line of a session, e.g. `12.345 > 58 C4`.
+    pub fn parse(line: &str) -> Result<RecordedData> {
+        let invalid = || Error::from(io::Error::new(io::ErrorKind::InvalidData, format!("Invalid session line: {}", line)));
+
+        let mut parts = line.split_whitespace();
+
+        let time = match parts.next().map(|value| value.parse::<f64>()) {
+            Some(Ok(time)) if time >= 0.0 => Duration::from_secs_f64(time / 1000.0),
+            _ => return Err(invalid())
+        };
+
+        let direction = match parts.next() {
+            Some(">") => Direction::Sent,
+            Some("<") => Direction::Received,
+            _ => return Err(invalid())
+        };
+
+        let mut data = Vec::new();
+        for value in parts {
+            data.push(u8::from_str_radix(value, 16).map_err(|_| invalid())?);
+        }
+
+        return Ok(RecordedData {
+            time,
+            direction,
+            data
+        });
+    }
+}
+
+impl fmt::Display for RecordedData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let direction = match self.direction {
+            Direction::Sent => ">",
+            Direction::Received => "<"
+        };
+
+        write!(f, "{:.3} {} {}", self.time.as_secs_f64() * 1000.0, direction, hex(&self.data))
+    }
+}
+
+/// Parse a recorded session, one `RecordedData` per line. Empty lines and lines starting with `#` are skipped.
+pub fn parse_session(session: &str) -> Result<Vec<RecordedData>> {
+    let mut result = Vec::new();
+
+    for line in session.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        result.push(RecordedData::parse(line)?);
+    }
+
+    return Ok(result);
+}
+
+/// Transport which writes all data exchanged over another transport to a session, e.g. a file.
+pub struct RecordingTransport<T: Transport, W: Write> {
+    transport: T,
+    session: W,
+    start: Instant
+}
+
+impl<T: Transport, W: Write> RecordingTransport<T, W> {
+    pub fn new(transport: T, session: W) -> RecordingTransport<T, W> {
+        return RecordingTransport {
+            transport,
+            session,
+            start: Instant::now()
+        };
+    }
+
+    pub fn into_inner(self) -> (T, W) {
+        (self.transport, self.session)
+    }
+
+    fn record(&mut self, direction: Direction, data: &[u8]) -> io::Result<()> {
+        let recorded = RecordedData {
+            time: self.start.elapsed(),
+            direction,
+            data: data.to_vec()
+        };
+
+        writeln!(self.session, "{}", recorded)?;
+
+        return self.session.flush();
+    }
+}
+
+impl<T: Transport, W: Write> Read for RecordingTransport<T, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.transport.read(buf)?;
+
+        if count > 0 {
+            self.record(Direction::Received, &buf[..count])?;
+        }
+
+        return Ok(count);
+    }
+}
+
+impl<T: Transport, W: Write> Write for RecordingTransport<T, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = self.transport.write(buf)?;
+
+        if count > 0 {
+            self.record(Direction::Sent, &buf[..count])?;
+        }
+
+        return Ok(count);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.transport.flush()
+    }
+}
+
+impl<T: Transport, W: Write> Transport for RecordingTransport<T, W> {
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.transport.set_timeout(timeout)
+    }
+}
+
+/// Transport which plays back a recorded session instead of talking to a device.
+///
+/// Sent data has to match the recorded data, otherwise writing fails. Data received after sent data becomes readable
+/// once all of it was written, reads time out like a serial port while nothing is left to read. Recorded times are
+/// not replayed.
+pub struct ReplayTransport {
+    session: VecDeque<RecordedData>,
+    expected: VecDeque<u8>,
+    received: VecDeque<u8>
+}
+
+impl ReplayTransport {
+    pub fn new(session: Vec<RecordedData>) -> ReplayTransport {
+        let mut transport = ReplayTransport {
+            session: session.into(),
+            expected: VecDeque::new(),
+            received: VecDeque::new()
+        };
+
+        // data the device sent before the first command
+        transport.receive();
+
+        return transport;
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<ReplayTransport> {
+        let session = fs::read_to_string(path)?;
+
+        return Ok(ReplayTransport::new(parse_session(&session)?));
+    }
+
+    /// Whether all recorded data was sent and read.
+    pub fn is_finished(&self) -> bool {
+        self.session.is_empty() && self.expected.is_empty() && self.received.is_empty()
+    }
+
+    // Make received data readable up to the next sent data
+    fn receive(&mut self) {
+        while let Some(recorded) = self.session.front() {
+            if recorded.direction == Direction::Sent {
+                break;
+            }
+
+            self.received.extend(&self.session.pop_front().unwrap().data);
+        }
+    }
+
+    fn next_expected(&mut self) -> Option<u8> {
+        if self.expected.is_empty() {
+            self.receive();
+
+            let recorded = self.session.pop_front()?;
+            self.expected.extend(&recorded.data);
+        }
+
+        return self.expected.pop_front();
+    }
+}
+
+impl Read for ReplayTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.received.is_empty() {
+            return Err(io::Error::from(io::ErrorKind::TimedOut));
+        }
+
+        let length = buf.len().min(self.received.len());
+        for (i, value) in self.received.drain(..length).enumerate() {
+            buf[i] = value;
+        }
+
+        return Ok(length);
+    }
+}
+
+impl Write for ReplayTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for value in buf {
+            match self.next_expected() {
+                Some(expected) if expected == *value => (),
+                Some(expected) => {
+                    let message = format!("Sent {:02X} instead of recorded {:02X}", value, expected);
+
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+                },
+                None => return Err(io::Error::new(io::ErrorKind::InvalidData, "Sent data after the end of the session"))
+            }
+        }
+
+        if self.expected.is_empty() {
+            self.receive();
+        }
+
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return Ok(());
+    }
+}
+
+impl Transport for ReplayTransport {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Device, P50XBinary, XLokOptions};
+    use crate::sim::Simulator;
+
+    #[test]
+    fn recorded_session_is_replayed() {
+        let mut simulator = Simulator::new();
+        simulator.set_power(true);
+
+        let transport = RecordingTransport::new(simulator, Vec::new());
+        let mut device = Device::from_transport(transport).unwrap();
+        device.xlok(3, 20, XLokOptions::default()).unwrap();
+        let status = device.xlok_status(3).unwrap();
+
+        let (_, session) = device.into_transport().into_inner();
+        let session = parse_session(&String::from_utf8(session).unwrap()).unwrap();
+        assert_eq!(session[0].direction, Direction::Sent);
+        assert_eq!(session[0].data, vec![0x58, 0xC4]);
+        assert_eq!(RecordedData::parse(&session[0].to_string()).unwrap().data, session[0].data);
+
+        let mut device = Device::from_transport(ReplayTransport::new(session.clone())).unwrap();
+        device.xlok(3, 20, XLokOptions::default()).unwrap();
+        assert_eq!(device.xlok_status(3).unwrap().speed, status.speed);
+        assert!(device.into_transport().is_finished());
+
+        // commands which differ from the session fail
+        let mut device = Device::from_transport(ReplayTransport::new(session)).unwrap();
+        let error = device.xlok(4, 20, XLokOptions::default()).unwrap_err();
+        assert_eq!(error.opcode(), Some(0x80));
+
+        match error.root() {
+            Error::Io(ref err) if err.kind() == io::ErrorKind::InvalidData => (),
+            root => panic!("Unexpected error {:?}", root)
+        }
+    }
+}
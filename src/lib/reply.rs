@@ -30,26 +30,34 @@ use std::convert::From;
 use std::fmt;
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
-#[repr(u8)]
 pub enum P50XReply {
-    Ok = 0x00,
-    BadCommand = 0x01,
-    BadParameter = 0x02,
-    PowerOff = 0x06,
-    NoLokCommandSpace = 0x08,
-    FullTurnoutCommandStack = 0x09,
-    NoData = 0x0A,
-    NoSlot = 0x0B,
-    BadLokParameter = 0x0C,
-    LokBusy = 0x0D,
-    BadTurnoutParameter = 0x0E,
-    BadSpecialOptionValue = 0x0F,
-    NoI2CCommandSpace = 0x10,
-    LowTurnoutCommandStackSpace = 0x40,
-    LokHalt = 0x41,
-    LokPowerOff = 0x42,
-    Busy = 0x80,
-    Unknown
+    Ok,
+    BadCommand,
+    BadParameter,
+    PowerOff,
+    NoLokCommandSpace,
+    FullTurnoutCommandStack,
+    NoData,
+    NoSlot,
+    BadLokParameter,
+    LokBusy,
+    BadTurnoutParameter,
+    BadSpecialOptionValue,
+    NoI2CCommandSpace,
+    LowTurnoutCommandStackSpace,
+    LokHalt,
+    LokPowerOff,
+    Busy,
+    ProgrammingTerminated,
+    ProgrammingBusy,
+    ProgrammingBitError,
+    ProgrammingError,
+    ProgrammingNoDecoder,
+    ProgrammingShortCircuit,
+    ProgrammingNoAcknowledge,
+    ProgrammingTimeout,
+    /// Reply code not known to this library
+    Unknown(u8)
 }
 
 impl From<u8> for P50XReply {
@@ -72,7 +80,48 @@ impl From<u8> for P50XReply {
             0x41 => P50XReply::LokHalt,
             0x42 => P50XReply::LokPowerOff,
             0x80 => P50XReply::Busy,
-            _ => P50XReply::Unknown
+            0xF3 => P50XReply::ProgrammingTerminated,
+            0xF4 => P50XReply::ProgrammingBusy,
+            0xFA => P50XReply::ProgrammingBitError,
+            0xFB => P50XReply::ProgrammingError,
+            0xFC => P50XReply::ProgrammingNoDecoder,
+            0xFD => P50XReply::ProgrammingShortCircuit,
+            0xFE => P50XReply::ProgrammingNoAcknowledge,
+            0xFF => P50XReply::ProgrammingTimeout,
+            _ => P50XReply::Unknown(value)
+        }
+    }
+}
+
+impl From<P50XReply> for u8 {
+    fn from(reply: P50XReply) -> u8 {
+        match reply {
+            P50XReply::Ok => 0x00,
+            P50XReply::BadCommand => 0x01,
+            P50XReply::BadParameter => 0x02,
+            P50XReply::PowerOff => 0x06,
+            P50XReply::NoLokCommandSpace => 0x08,
+            P50XReply::FullTurnoutCommandStack => 0x09,
+            P50XReply::NoData => 0x0A,
+            P50XReply::NoSlot => 0x0B,
+            P50XReply::BadLokParameter => 0x0C,
+            P50XReply::LokBusy => 0x0D,
+            P50XReply::BadTurnoutParameter => 0x0E,
+            P50XReply::BadSpecialOptionValue => 0x0F,
+            P50XReply::NoI2CCommandSpace => 0x10,
+            P50XReply::LowTurnoutCommandStackSpace => 0x40,
+            P50XReply::LokHalt => 0x41,
+            P50XReply::LokPowerOff => 0x42,
+            P50XReply::Busy => 0x80,
+            P50XReply::ProgrammingTerminated => 0xF3,
+            P50XReply::ProgrammingBusy => 0xF4,
+            P50XReply::ProgrammingBitError => 0xFA,
+            P50XReply::ProgrammingError => 0xFB,
+            P50XReply::ProgrammingNoDecoder => 0xFC,
+            P50XReply::ProgrammingShortCircuit => 0xFD,
+            P50XReply::ProgrammingNoAcknowledge => 0xFE,
+            P50XReply::ProgrammingTimeout => 0xFF,
+            P50XReply::Unknown(value) => value
         }
     }
 }
@@ -97,7 +146,30 @@ impl fmt::Display for P50XReply {
             P50XReply::LokHalt => write!(f, "Command accepted but in halt mode"),
             P50XReply::LokPowerOff => write!(f, "Command accepted but power is off"),
             P50XReply::Busy => write!(f, "Device is busy"),
-            P50XReply::Unknown => write!(f, "Unknown error")
+            P50XReply::ProgrammingTerminated => write!(f, "Programming task was terminated"),
+            P50XReply::ProgrammingBusy => write!(f, "Programming track is busy"),
+            P50XReply::ProgrammingBitError => write!(f, "Error while reading bit from decoder"),
+            P50XReply::ProgrammingError => write!(f, "Programming error"),
+            P50XReply::ProgrammingNoDecoder => write!(f, "No decoder detected on programming track"),
+            P50XReply::ProgrammingShortCircuit => write!(f, "Short circuit on programming track"),
+            P50XReply::ProgrammingNoAcknowledge => write!(f, "No acknowledge from decoder"),
+            P50XReply::ProgrammingTimeout => write!(f, "Programming timeout"),
+            P50XReply::Unknown(value) => write!(f, "Unknown error 0x{:02X}", value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reply_codes_are_kept() {
+        for value in 0..=255u8 {
+            assert_eq!(u8::from(P50XReply::from(value)), value);
         }
+
+        assert_eq!(P50XReply::from(0x81), P50XReply::Unknown(0x81));
+        assert!(P50XReply::from(0x80) != P50XReply::Unknown(0x80));
     }
 }
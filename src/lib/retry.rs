@@ -0,0 +1,115 @@
+/*
+ * File: retry.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::time::Duration;
+
+use super::reply::P50XReply;
+
+/// Replies which only mean the device can not take the command right now.
+pub const TRANSIENT_REPLIES: [P50XReply; 4] = [
+    P50XReply::Busy,
+    P50XReply::NoLokCommandSpace,
+    P50XReply::FullTurnoutCommandStack,
+    P50XReply::NoI2CCommandSpace
+];
+
+/// When a device sends a command again after it was rejected.
+///
+/// The delay before each retry is multiplied by `backoff` after every attempt, but never exceeds `max_delay`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Number of times a command is sent at most, including the first attempt.
+    pub max_attempts: u32,
+    pub delay: Duration,
+    pub backoff: u32,
+    pub max_delay: Duration,
+    pub retryable: Vec<P50XReply>
+}
+
+impl RetryPolicy {
+    /// Policy which sends every command only once.
+    pub fn none() -> RetryPolicy {
+        return RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        };
+    }
+
+    pub fn is_retryable(&self, reply: P50XReply) -> bool {
+        self.retryable.contains(&reply)
+    }
+
+    /// Delay before sending a command again after the given failed attempt, starting at 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let mut delay = self.delay;
+
+        for _ in 1..attempt {
+            delay = delay.saturating_mul(self.backoff);
+
+            if delay >= self.max_delay {
+                break;
+            }
+        }
+
+        return delay.min(self.max_delay);
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            delay: Duration::from_millis(10),
+            backoff: 2,
+            max_delay: Duration::from_millis(500),
+            retryable: TRANSIENT_REPLIES.to_vec()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_grows_until_max_delay() {
+        let policy = RetryPolicy {
+            delay: Duration::from_millis(100),
+            backoff: 3,
+            max_delay: Duration::from_millis(1000),
+            ..Default::default()
+        };
+
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(300));
+        assert_eq!(policy.delay(3), Duration::from_millis(900));
+        assert_eq!(policy.delay(4), Duration::from_millis(1000));
+        assert!(policy.is_retryable(P50XReply::Busy));
+        assert!(policy.is_retryable(P50XReply::BadParameter) == false);
+    }
+}
@@ -0,0 +1,725 @@
+/*
+ * File: shared.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::mpsc::channel;
+use std::thread::{self, ThreadId};
+
+use super::capabilities::Capabilities;
+use super::error::*;
+use super::outcome::Outcome;
+use super::protocol::*;
+use super::retry::RetryPolicy;
+use super::version::VersionInfo;
+
+type Job<D> = Box<dyn FnOnce(&mut D) + Send>;
+
+/// Order in which queued commands are sent, commands with the same priority are sent in the order they were queued.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Priority {
+    /// Bulk polling like s88 scans or status reads.
+    Low,
+    #[default]
+    Normal,
+    /// Safety commands which must not wait behind other commands.
+    High
+}
+
+struct QueuedJob<D> {
+    priority: Priority,
+    sequence: u64,
+    job: Job<D>
+}
+
+impl<D> QueuedJob<D> {
+    fn key(&self) -> (Priority, Reverse<u64>) {
+        (self.priority, Reverse(self.sequence))
+    }
+}
+
+impl<D> PartialEq for QueuedJob<D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl<D> Eq for QueuedJob<D> {}
+
+impl<D> PartialOrd for QueuedJob<D> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<D> Ord for QueuedJob<D> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+struct QueueState<D> {
+    jobs: BinaryHeap<QueuedJob<D>>,
+    sequence: u64,
+    handles: usize
+}
+
+struct Queue<D> {
+    state: Mutex<QueueState<D>>,
+    available: Condvar
+}
+
+impl<D> Queue<D> {
+    fn push(&self, priority: Priority, job: Job<D>) {
+        let mut state = self.state.lock().unwrap();
+
+        let sequence = state.sequence;
+        state.sequence += 1;
+        state.jobs.push(QueuedJob { priority, sequence, job });
+
+        self.available.notify_one();
+    }
+
+    // Wait for the next job, returns none once all handles are dropped and the queue is empty
+    fn pop(&self) -> Option<Job<D>> {
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            if let Some(queued) = state.jobs.pop() {
+                return Some(queued.job);
+            }
+
+            if state.handles == 0 {
+                return None;
+            }
+
+            state = self.available.wait(state).unwrap();
+        }
+    }
+}
+
+/// Cloneable handle to a device which is used from multiple threads.
+///
+/// The device is owned by a background thread which runs the queued commands one after another, so each reply is
+/// read by the command which requested it. Sequences of commands, e.g. reading and changing a function group, are
+/// only run without other commands in between when passed to `execute` together. The thread stops when the last
+/// handle is dropped. A command which panics fails with `Error::Other` and the thread keeps running.
+///
+/// Queued commands are sent by priority. Power off, halt and emergency stops are always sent with `Priority::High`,
+/// all other commands with the priority of the handle. A command which is already being sent is not interrupted, so
+/// a safety command waits for at most one other command.
+///
+/// A retry policy of the device itself waits between attempts while the command is being sent, which delays safety
+/// commands as well. Use `set_retry_policy` of the handle instead, which waits in the calling thread so other commands
+/// are sent in between. Commands passed to `execute` are not retried by the handle.
+pub struct SharedDevice<D: P50XBinary + Send + 'static> {
+    queue: Arc<Queue<D>>,
+    priority: Priority,
+    retry_policy: RetryPolicy,
+    capabilities: Arc<Mutex<Option<Capabilities>>>,
+    worker: ThreadId
+}
+
+impl<D: P50XBinary + Send + 'static> SharedDevice<D> {
+    pub fn new(device: D) -> SharedDevice<D> {
+        let capabilities = Arc::new(Mutex::new(device.capabilities()));
+        let queue = Arc::new(Queue {
+            state: Mutex::new(QueueState {
+                jobs: BinaryHeap::new(),
+                sequence: 0,
+                handles: 1
+            }),
+            available: Condvar::new()
+        });
+
+        let worker_queue = queue.clone();
+        let worker = thread::spawn(move || {
+            let mut device = device;
+
+            while let Some(job) = worker_queue.pop() {
+                // a panicking command drops its reply channel, so only its caller gets an error
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| job(&mut device)));
+            }
+        }).thread().id();
+
+        return SharedDevice {
+            queue,
+            priority: Priority::Normal,
+            retry_policy: RetryPolicy::none(),
+            capabilities,
+            worker
+        };
+    }
+
+    /// Create a handle which sends its commands with the given priority, e.g. `Priority::Low` for a sensor poller.
+    pub fn with_priority(&self, priority: Priority) -> SharedDevice<D> {
+        let mut device = self.clone();
+        device.priority = priority;
+
+        return device;
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Run commands on the device without commands of other handles in between.
+    ///
+    /// The commands must use the device they are passed, calling a handle from within them would wait for
+    /// themselves. This panics, so the calling command fails with `Error::Other`.
+    pub fn execute<R, F>(&self, command: F) -> Result<R> where R: Send + 'static, F: FnOnce(&mut D) -> Result<R> + Send + 'static {
+        self.execute_with_priority(self.priority, command)
+    }
+
+    pub fn execute_with_priority<R, F>(&self, priority: Priority, command: F) -> Result<R> where R: Send + 'static, F: FnOnce(&mut D) -> Result<R> + Send + 'static {
+        assert!(thread::current().id() != self.worker, "SharedDevice used from within one of its own commands");
+
+        let (sender, receiver) = channel();
+        let capabilities = self.capabilities.clone();
+
+        self.queue.push(priority, Box::new(move |device| {
+            let result = command(device);
+
+            // capabilities change when they are probed or the device is reconnected
+            *capabilities.lock().unwrap() = device.capabilities();
+
+            // the caller is gone if sending fails, so there is no one left to report to
+            let _ = sender.send(result);
+        }));
+
+        return receiver.recv().unwrap_or(Err(Error::Other));
+    }
+
+    // Queue a single command and queue it again while it is rejected with a retryable reply
+    fn command<R, F>(&self, priority: Priority, command: F) -> Result<R> where R: Send + 'static, F: Fn(&mut D) -> Result<R> + Send + Sync + 'static {
+        let command = Arc::new(command);
+        let mut attempt = 1;
+
+        loop {
+            let queued = command.clone();
+            let result = self.execute_with_priority(priority, move |device| queued(device));
+
+            let retry = match result {
+                Err(ref err) => match err.reply() {
+                    Some(reply) => attempt < self.retry_policy.max_attempts && self.retry_policy.is_retryable(reply),
+                    None => false
+                },
+                Ok(_) => false
+            };
+
+            if retry == false {
+                return result;
+            }
+
+            // the worker sends commands of other handles while this one waits
+            thread::sleep(self.retry_policy.delay(attempt));
+            attempt += 1;
+        }
+    }
+}
+
+impl<D: P50XBinary + Send + 'static> Clone for SharedDevice<D> {
+    fn clone(&self) -> Self {
+        self.queue.state.lock().unwrap().handles += 1;
+
+        SharedDevice {
+            queue: self.queue.clone(),
+            priority: self.priority,
+            retry_policy: self.retry_policy.clone(),
+            capabilities: self.capabilities.clone(),
+            worker: self.worker
+        }
+    }
+}
+
+impl<D: P50XBinary + Send + 'static> Drop for SharedDevice<D> {
+    fn drop(&mut self) {
+        self.queue.state.lock().unwrap().handles -= 1;
+        self.queue.available.notify_one();
+    }
+}
+
+impl<D: P50XBinary + Send + 'static> P50XBinary for SharedDevice<D> {
+    fn xpower_off(&mut self) -> Result<()> {
+        self.command(Priority::High, |device| device.xpower_off())
+    }
+
+    fn xpower_on(&mut self) -> Result<()> {
+        self.command(self.priority, |device| device.xpower_on())
+    }
+
+    fn xhalt(&mut self) -> Result<()> {
+        self.command(Priority::High, |device| device.xhalt())
+    }
+
+    fn xso_set(&mut self, special_option: u16, value: u8) -> Result<()> {
+        self.command(self.priority, move |device| device.xso_set(special_option, value))
+    }
+
+    fn xso_get(&mut self, special_option: u16) -> Result<u8> {
+        self.command(self.priority, move |device| device.xso_get(special_option))
+    }
+
+    fn xversion(&mut self) -> Result<VersionInfo> {
+        self.command(self.priority, |device| device.xversion())
+    }
+
+    fn xp50xch(&mut self, extended_character: u8) -> Result<()> {
+        self.command(self.priority, move |device| device.xp50xch(extended_character))
+    }
+
+    fn xstatus(&mut self) -> Result<DeviceStatus> {
+        self.command(self.priority, |device| device.xstatus())
+    }
+
+    fn xnop(&mut self) -> Result<()> {
+        self.command(self.priority, |device| device.xnop())
+    }
+
+    fn xsensor(&mut self, module: u8) -> Result<[bool; 16]> {
+        self.command(self.priority, move |device| device.xsensor(module))
+    }
+
+    fn xsens_off(&mut self) -> Result<()> {
+        self.command(self.priority, |device| device.xsens_off())
+    }
+
+    fn x88p_get(&mut self, parameter: u8) -> Result<u8> {
+        self.command(self.priority, move |device| device.x88p_get(parameter))
+    }
+
+    fn x88p_set(&mut self, parameter: u8, value: u8) -> Result<()> {
+        self.command(self.priority, move |device| device.x88p_set(parameter, value))
+    }
+
+    fn xs88_timer(&mut self, timer: u8, reset: bool) -> Result<u16> {
+        self.command(self.priority, move |device| device.xs88_timer(timer, reset))
+    }
+
+    fn xs88_count(&mut self, timer: u8, reset: bool) -> Result<u16> {
+        self.command(self.priority, move |device| device.xs88_count(timer, reset))
+    }
+
+    fn xlok(&mut self, address: u16, speed: i8, options: XLokOptions) -> Result<Outcome<()>> {
+        let priority = if options.emergency_stop { Priority::High } else { self.priority };
+
+        self.command(priority, move |device| device.xlok(address, speed, options))
+    }
+
+    fn xlok_status(&mut self, address: u16) -> Result<XLokStatus> {
+        self.command(self.priority, move |device| device.xlok_status(address))
+    }
+
+    fn xlok_config(&mut self, address: u16) -> Result<XLokConfig> {
+        self.command(self.priority, move |device| device.xlok_config(address))
+    }
+
+    fn xlok_set_config(&mut self, address: u16, config: XLokConfig) -> Result<()> {
+        self.command(self.priority, move |device| device.xlok_set_config(address, config))
+    }
+
+    fn xlok_dispatch(&mut self, address: u16) -> Result<Option<u8>> {
+        self.command(self.priority, move |device| device.xlok_dispatch(address))
+    }
+
+    fn xfunc(&mut self, address: u16, functions: [bool; 8]) -> Result<Outcome<()>> {
+        self.command(self.priority, move |device| device.xfunc(address, functions))
+    }
+
+    fn xfunc_status(&mut self, address: u16) -> Result<[bool; 8]> {
+        self.command(self.priority, move |device| device.xfunc_status(address))
+    }
+
+    fn xfuncx(&mut self, address: u16, functions: [bool; 8]) -> Result<Outcome<()>> {
+        self.command(self.priority, move |device| device.xfuncx(address, functions))
+    }
+
+    fn xfuncx_status(&mut self, address: u16) -> Result<[bool; 8]> {
+        self.command(self.priority, move |device| device.xfuncx_status(address))
+    }
+
+    fn xfunc34(&mut self, address: u16, functions: [bool; 16]) -> Result<Outcome<()>> {
+        self.command(self.priority, move |device| device.xfunc34(address, functions))
+    }
+
+    fn xfunc34_status(&mut self, address: u16) -> Result<[bool; 16]> {
+        self.command(self.priority, move |device| device.xfunc34_status(address))
+    }
+
+    fn xturnout(&mut self, address: u16, state: bool, options: XTurnoutOptions) -> Result<Outcome<()>> {
+        self.command(self.priority, move |device| device.xturnout(address, state, options))
+    }
+
+    fn xturnout_free(&mut self) -> Result<()> {
+        self.command(self.priority, |device| device.xturnout_free())
+    }
+
+    fn xturnout_status(&mut self, address: u16) -> Result<XTurnoutStatus> {
+        self.command(self.priority, move |device| device.xturnout_status(address))
+    }
+
+    fn xturnout_group(&mut self, group_address: u8) -> Result<[(bool, bool); 8]> {
+        self.command(self.priority, move |device| device.xturnout_group(group_address))
+    }
+
+    fn xevent(&mut self) -> Result<XEventStatus> {
+        self.command(self.priority, |device| device.xevent())
+    }
+
+    fn xevt_lok(&mut self) -> Result<Vec<XLokEvent>> {
+        self.command(self.priority, |device| device.xevt_lok())
+    }
+
+    fn xevt_turnout(&mut self) -> Result<Vec<XTurnoutEvent>> {
+        self.command(self.priority, |device| device.xevt_turnout())
+    }
+
+    fn xevt_sensor(&mut self) -> Result<Vec<XSensorEvent>> {
+        self.command(self.priority, |device| device.xevt_sensor())
+    }
+
+    fn xevt_pt(&mut self) -> Result<Option<XPTEvent>> {
+        self.command(self.priority, |device| device.xevt_pt())
+    }
+
+    fn xpt_term(&mut self) -> Result<()> {
+        self.command(self.priority, |device| device.xpt_term())
+    }
+
+    fn xpt_motorola_write_register(&mut self, register: u8, value: u8) -> Result<()> {
+        self.command(self.priority, move |device| device.xpt_motorola_write_register(register, value))
+    }
+
+    fn xpt_dcc_read_register(&mut self, register: u8) -> Result<()> {
+        self.command(self.priority, move |device| device.xpt_dcc_read_register(register))
+    }
+
+    fn xpt_dcc_write_register(&mut self, register: u8, value: u8) -> Result<()> {
+        self.command(self.priority, move |device| device.xpt_dcc_write_register(register, value))
+    }
+
+    fn xpt_dcc_read_page(&mut self, cv: u16) -> Result<()> {
+        self.command(self.priority, move |device| device.xpt_dcc_read_page(cv))
+    }
+
+    fn xpt_dcc_write_page(&mut self, cv: u16, value: u8) -> Result<()> {
+        self.command(self.priority, move |device| device.xpt_dcc_write_page(cv, value))
+    }
+
+    fn xpt_dcc_read_physical_register(&mut self, register: u8) -> Result<()> {
+        self.command(self.priority, move |device| device.xpt_dcc_read_physical_register(register))
+    }
+
+    fn xpt_dcc_write_physical_register(&mut self, register: u8, value: u8) -> Result<()> {
+        self.command(self.priority, move |device| device.xpt_dcc_write_physical_register(register, value))
+    }
+
+    fn xpt_dcc_read_byte(&mut self, cv: u16) -> Result<()> {
+        self.command(self.priority, move |device| device.xpt_dcc_read_byte(cv))
+    }
+
+    fn xpt_dcc_read_bit(&mut self, cv: u16, bit: u8) -> Result<()> {
+        self.command(self.priority, move |device| device.xpt_dcc_read_bit(cv, bit))
+    }
+
+    fn xpt_dcc_write_bit(&mut self, cv: u16, bit: u8, value: bool) -> Result<()> {
+        self.command(self.priority, move |device| device.xpt_dcc_write_bit(cv, bit, value))
+    }
+
+    fn xpt_dcc_write_byte(&mut self, cv: u16, value: u8) -> Result<()> {
+        self.command(self.priority, move |device| device.xpt_dcc_write_byte(cv, value))
+    }
+
+    fn xpom_read(&mut self, address: u16, cv: u16) -> Result<u8> {
+        self.command(self.priority, move |device| device.xpom_read(address, cv))
+    }
+
+    fn xpom_write(&mut self, address: u16, cv: u16, value: u8) -> Result<()> {
+        self.command(self.priority, move |device| device.xpom_write(address, cv, value))
+    }
+
+    fn xpom_write_bit(&mut self, address: u16, cv: u16, bit: u8, value: bool) -> Result<()> {
+        self.command(self.priority, move |device| device.xpom_write_bit(address, cv, bit, value))
+    }
+
+    fn capabilities(&self) -> Option<Capabilities> {
+        return *self.capabilities.lock().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use super::*;
+    use crate::Device;
+    use crate::functions::LokFunctions;
+    use crate::sim::Simulator;
+    use crate::sim::testing::TestSimulator;
+
+    fn queued_jobs<D: P50XBinary + Send + 'static>(device: &SharedDevice<D>) -> usize {
+        device.queue.state.lock().unwrap().jobs.len()
+    }
+
+    #[test]
+    fn commands_from_many_threads_get_their_replies() {
+        let mut simulator = Simulator::new();
+        simulator.set_power(true);
+
+        let shared = SharedDevice::new(Device::from_transport(simulator).unwrap());
+
+        let handles: Vec<_> = (1..=8).map(|address| {
+            let mut device = shared.clone();
+
+            thread::spawn(move || {
+                for speed in 0..20 {
+                    device.xlok(address, speed, XLokOptions::default()).unwrap();
+                    assert_eq!(device.xlok_status(address).unwrap().speed, speed);
+                    device.set_function(address, 9, speed % 2 == 1).unwrap();
+                }
+            })
+        }).collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut device = shared;
+        for address in 1..=8 {
+            assert_eq!(device.xlok_status(address).unwrap().speed, 19);
+            assert!(device.function(address, 9).unwrap());
+        }
+
+        let functions = device.execute(|device| device.functions(1)).unwrap();
+        assert!(functions[9]);
+    }
+
+    #[test]
+    fn panicking_command_does_not_stop_the_worker() {
+        let shared = SharedDevice::new(Device::from_transport(Simulator::new()).unwrap());
+
+        let result: Result<()> = shared.execute(|_| panic!("Command failed"));
+        match result {
+            Err(Error::Other) => (),
+            _ => panic!("Unexpected result {:?}", result)
+        }
+
+        shared.execute(|device| device.xnop()).unwrap();
+    }
+
+    #[test]
+    fn nested_commands_fail_instead_of_waiting_for_themselves() {
+        let mut shared = SharedDevice::new(Device::from_transport(Simulator::new()).unwrap());
+        let mut handle = shared.clone();
+
+        let result = shared.execute(move |_| handle.xnop());
+        match result {
+            Err(Error::Other) => (),
+            _ => panic!("Unexpected result {:?}", result)
+        }
+
+        shared.xnop().unwrap();
+    }
+
+    #[test]
+    fn capabilities_follow_the_device() {
+        let shared = SharedDevice::new(Device::from_transport(Simulator::new()).unwrap());
+        let handle = shared.clone();
+        assert_eq!(handle.capabilities(), None);
+
+        shared.execute(|device| device.probe_capabilities()).unwrap();
+        assert_eq!(handle.capabilities(), Some(Capabilities::all()));
+
+        shared.execute(|device| {
+            device.set_capabilities(Some(Capabilities::EVENTS));
+
+            return Ok(());
+        }).unwrap();
+        assert_eq!(handle.capabilities(), Some(Capabilities::EVENTS));
+    }
+
+    #[test]
+    fn safety_commands_jump_the_queue() {
+        let mut simulator = Simulator::new();
+        simulator.set_power(true);
+
+        let shared = SharedDevice::new(Device::from_transport(simulator).unwrap());
+
+        // keep the device busy until all commands are queued
+        let (started_sender, started) = channel();
+        let (release, blocked) = channel::<()>();
+        let blocker = shared.clone();
+        let busy = thread::spawn(move || blocker.execute(move |_| {
+            started_sender.send(()).unwrap();
+            blocked.recv().unwrap();
+
+            return Ok(());
+        }));
+        started.recv().unwrap();
+
+        let pollers: Vec<_> = (0..5).map(|_| {
+            let poller = shared.with_priority(Priority::Low);
+
+            thread::spawn(move || poller.execute(|device| Ok(device.xstatus()?.halt)))
+        }).collect();
+
+        while queued_jobs(&shared) < 5 {
+            thread::yield_now();
+        }
+
+        let mut halter = shared.clone();
+        let halt = thread::spawn(move || halter.xhalt());
+
+        while queued_jobs(&shared) < 6 {
+            thread::yield_now();
+        }
+
+        release.send(()).unwrap();
+        busy.join().unwrap().unwrap();
+        halt.join().unwrap().unwrap();
+
+        // every poll was sent after the halt
+        for poller in pollers {
+            assert!(poller.join().unwrap().unwrap());
+        }
+    }
+
+    #[test]
+    fn rejected_commands_are_retried_without_blocking_other_handles() {
+        let mut simulator = Simulator::new();
+        simulator.set_power(true);
+
+        // the rejection waits until another command is queued, so it is sent before the retry is queued again
+        let (rejected_sender, rejected) = channel();
+        let (queued, queued_receiver) = channel();
+
+        let mut transport = TestSimulator::new(simulator);
+        let opcodes = transport.opcodes.clone();
+        transport.rejections = 1;
+        transport.on_rejection = Some(Box::new(move || {
+            rejected_sender.send(()).unwrap();
+            queued_receiver.recv().unwrap();
+        }));
+
+        let mut shared = SharedDevice::new(Device::from_transport(transport).unwrap());
+        shared.set_retry_policy(RetryPolicy {
+            max_attempts: 2,
+            delay: Duration::from_millis(1),
+            ..Default::default()
+        });
+        opcodes.lock().unwrap().clear();
+
+        let mut device = shared.clone();
+        let turnout = thread::spawn(move || device.xturnout(5, true, XTurnoutOptions::default()));
+        rejected.recv().unwrap();
+
+        let mut device = shared.clone();
+        let nop = thread::spawn(move || device.xnop());
+
+        while queued_jobs(&shared) < 1 {
+            thread::yield_now();
+        }
+
+        queued.send(()).unwrap();
+
+        nop.join().unwrap().unwrap();
+        turnout.join().unwrap().unwrap();
+
+        assert_eq!(*opcodes.lock().unwrap(), [0x90, 0xC4, 0x90]);
+        assert!(shared.xturnout_status(5).unwrap().state);
+    }
+
+    #[test]
+    fn emergency_stop_is_sent_before_queued_commands() {
+        let mut simulator = Simulator::new();
+        simulator.set_power(true);
+
+        let transport = TestSimulator::new(simulator);
+        let opcodes = transport.opcodes.clone();
+        let shared = SharedDevice::new(Device::from_transport(transport).unwrap());
+
+        // keep the device busy until all commands are queued
+        let (started_sender, started) = channel();
+        let (release, blocked) = channel::<()>();
+        let blocker = shared.clone();
+        let busy = thread::spawn(move || blocker.execute(move |_| {
+            started_sender.send(()).unwrap();
+            blocked.recv().unwrap();
+
+            return Ok(());
+        }));
+        started.recv().unwrap();
+
+        let pollers: Vec<_> = (0..8).map(|module| {
+            let mut poller = shared.clone();
+
+            thread::spawn(move || poller.xsensor(module + 1))
+        }).collect();
+
+        while queued_jobs(&shared) < 8 {
+            thread::yield_now();
+        }
+
+        let mut device = shared.clone();
+        let stop = thread::spawn(move || {
+            let options = XLokOptions { emergency_stop: true, ..Default::default() };
+
+            device.xlok(3, 0, options)
+        });
+
+        while queued_jobs(&shared) < 9 {
+            thread::yield_now();
+        }
+
+        opcodes.lock().unwrap().clear();
+        release.send(()).unwrap();
+        busy.join().unwrap().unwrap();
+        stop.join().unwrap().unwrap();
+
+        for poller in pollers {
+            poller.join().unwrap().unwrap();
+        }
+
+        // the emergency stop waited for the running command only
+        let opcodes = opcodes.lock().unwrap();
+        assert_eq!(opcodes.len(), 9);
+        assert_eq!(opcodes[0], 0x80);
+        assert!(opcodes[1..].iter().all(|opcode| *opcode == 0x98));
+    }
+}
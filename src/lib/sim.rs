@@ -0,0 +1,1541 @@
+/*
+ * File: sim.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! Simulated P50X command station.
+//!
+//! The simulator implements the device side of the P50Xb protocol with a stateful model of locomotives,
+//! turnouts, s88 modules, special options and power state. It can be used directly as the transport of a
+//! `Device` or serve any other byte stream, e.g. a tcp connection.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+
+use super::ascii::{fields, error_message};
+use super::legacy::{self, MAX_LEGACY_LOK_ADDRESS};
+use super::protocol::{ConnectionMode, XProtocol};
+use super::reply::P50XReply;
+use super::transport::Transport;
+
+const MAX_LOK_ADDRESS: u16 = 10239;
+const MAX_TURNOUT_ADDRESS: u16 = 2048;
+const MAX_SENSOR_MODULE: u8 = 31;
+
+#[derive(Debug, Copy, Clone)]
+struct SimLok {
+    speed: u8,
+    reverse: bool,
+    light: bool,
+    functions: u32,
+    protocol: XProtocol,
+    speed_steps: u8,
+    virtual_address: Option<u16>
+}
+
+impl Default for SimLok {
+    fn default() -> Self {
+        SimLok {
+            speed: 0,
+            reverse: false,
+            light: false,
+            functions: 0,
+            protocol: XProtocol::DCC,
+            speed_steps: 28,
+            virtual_address: None
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct SimTurnout {
+    state: bool,
+    reserved: bool,
+    protocol: XProtocol
+}
+
+impl Default for SimTurnout {
+    fn default() -> Self {
+        SimTurnout {
+            state: false,
+            reserved: false,
+            protocol: XProtocol::DCC
+        }
+    }
+}
+
+pub struct Simulator {
+    extended_character: u8,
+    power: bool,
+    halt: bool,
+    version: Vec<Vec<u8>>,
+    loks: HashMap<u16, SimLok>,
+    lok_cvs: HashMap<(u16, u16), u8>,
+    turnouts: HashMap<u16, SimTurnout>,
+    sensors: HashMap<u8, u16>,
+    special_options: HashMap<u16, u8>,
+    s88_parameters: HashMap<u8, u8>,
+    s88_timers: [u16; 16],
+    s88_counters: [u16; 16],
+    lok_events: Vec<u16>,
+    turnout_events: Vec<u16>,
+    sensor_events: Vec<u8>,
+    power_off_event: bool,
+    status_event: bool,
+    programming_decoder: bool,
+    programming_cvs: HashMap<u16, u8>,
+    programming_result: Option<(P50XReply, Option<u8>)>,
+    railcom: bool,
+    mode: ConnectionMode,
+    input: Vec<u8>,
+    output: VecDeque<u8>
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Simulator::new()
+    }
+}
+
+impl Simulator {
+    pub fn new() -> Simulator {
+        Simulator {
+            extended_character: 0x58,
+            power: false,
+            halt: false,
+            version: vec![vec![0x02, 0x00], vec![0x00, 0x00, 0x12, 0x34, 0x56]],
+            loks: HashMap::new(),
+            lok_cvs: HashMap::new(),
+            turnouts: HashMap::new(),
+            sensors: HashMap::new(),
+            special_options: HashMap::new(),
+            s88_parameters: HashMap::new(),
+            s88_timers: [0; 16],
+            s88_counters: [0; 16],
+            lok_events: Vec::new(),
+            turnout_events: Vec::new(),
+            sensor_events: Vec::new(),
+            power_off_event: false,
+            status_event: false,
+            programming_decoder: true,
+            programming_cvs: HashMap::new(),
+            programming_result: None,
+            railcom: false,
+            mode: ConnectionMode::Binary,
+            input: Vec::new(),
+            output: VecDeque::new()
+        }
+    }
+
+    pub fn power(&self) -> bool {
+        self.power
+    }
+
+    pub fn halt(&self) -> bool {
+        self.halt
+    }
+
+    pub fn set_power(&mut self, power: bool) {
+        if self.power && power == false {
+            self.power_off_event = true;
+        }
+
+        if self.power != power || self.halt {
+            self.status_event = true;
+        }
+
+        self.power = power;
+        self.halt = false;
+    }
+
+    fn stop_locomotives(&mut self) {
+        if self.halt == false {
+            self.status_event = true;
+        }
+
+        self.halt = true;
+    }
+
+    pub fn set_version(&mut self, version: Vec<Vec<u8>>) {
+        self.version = version;
+    }
+
+    pub fn sensor(&self, module: u8, contact: u8) -> bool {
+        let data = self.sensors.get(&module).copied().unwrap_or(0);
+
+        return data & (1 << contact) != 0;
+    }
+
+    /// Set the state of a single s88 contact, e.g. to simulate a train passing over it.
+    pub fn set_sensor(&mut self, module: u8, contact: u8, state: bool) {
+        let data = self.sensors.entry(module).or_insert(0);
+        let previous = *data;
+
+        if state {
+            *data |= 1 << contact;
+        } else {
+            *data &= !(1 << contact);
+        }
+
+        if *data != previous && self.sensor_events.contains(&module) == false {
+            self.sensor_events.push(module);
+        }
+    }
+
+    pub fn lok_speed(&self, address: u16) -> i8 {
+        let lok = self.loks.get(&address).copied().unwrap_or_default();
+
+        if lok.reverse {
+            -(lok.speed as i8)
+        } else {
+            lok.speed as i8
+        }
+    }
+
+    /// Whether a locomotive drives in reverse, which is also kept while it stands still.
+    pub fn lok_reverse(&self, address: u16) -> bool {
+        self.loks.get(&address).copied().unwrap_or_default().reverse
+    }
+
+    pub fn lok_light(&self, address: u16) -> bool {
+        self.loks.get(&address).copied().unwrap_or_default().light
+    }
+
+    /// Change the speed of a locomotive like an external controller, e.g. a handheld, would.
+    pub fn drive_lok(&mut self, address: u16, speed: i8) {
+        let lok = self.loks.entry(address).or_default();
+        lok.speed = speed.unsigned_abs();
+        lok.reverse = speed < 0;
+
+        if self.lok_events.contains(&address) == false {
+            self.lok_events.push(address);
+        }
+    }
+
+    pub fn turnout_state(&self, address: u16) -> bool {
+        self.turnouts.get(&address).map(|turnout| turnout.state).unwrap_or(false)
+    }
+
+    /// Switch a turnout like an external controller, e.g. a keyboard, would.
+    pub fn switch_turnout(&mut self, address: u16, state: bool) {
+        self.turnouts.entry(address).or_default().state = state;
+
+        if self.turnout_events.contains(&address) == false {
+            self.turnout_events.push(address);
+        }
+    }
+
+    /// Set whether a decoder is placed on the programming track.
+    pub fn set_programming_decoder(&mut self, present: bool) {
+        self.programming_decoder = present;
+    }
+
+    pub fn programming_cv(&self, cv: u16) -> u8 {
+        match self.programming_cvs.get(&cv) {
+            Some(value) => *value,
+            // defaults of a freshly reset decoder: address 3, 28 speed steps
+            None => match cv {
+                1 => 3,
+                29 => 6,
+                _ => 0
+            }
+        }
+    }
+
+    /// Set whether RailCom feedback is available to read decoder values on the main track.
+    pub fn set_railcom(&mut self, railcom: bool) {
+        self.railcom = railcom;
+    }
+
+    pub fn lok_cv(&self, address: u16, cv: u16) -> u8 {
+        return self.lok_cvs.get(&(address, cv)).copied().unwrap_or_default();
+    }
+
+    pub fn mode(&self) -> ConnectionMode {
+        self.mode
+    }
+
+    /// Set the accepted commands. In binary mode P50Xa command lines are accepted as well, in ASCII mode only
+    /// P50Xa and in legacy mode only original P50 commands are understood.
+    pub fn set_mode(&mut self, mode: ConnectionMode) {
+        self.mode = mode;
+    }
+
+    /// Serve the simulated device on the given stream until it is closed.
+    pub fn serve<S: Read + Write>(&mut self, stream: &mut S) -> io::Result<()> {
+        let mut buffer = [0; 256];
+
+        loop {
+            let count = match stream.read(&mut buffer) {
+                Ok(0) => return Ok(()),
+                Ok(count) => count,
+                Err(err) if err.kind() == io::ErrorKind::TimedOut || err.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err)
+            };
+
+            self.receive(&buffer[..count]);
+
+            let reply: Vec<u8> = self.output.drain(..).collect();
+            stream.write_all(&reply)?;
+            stream.flush()?;
+        }
+    }
+
+    fn receive(&mut self, data: &[u8]) {
+        self.input.extend_from_slice(data);
+
+        loop {
+            let consumed = self.process();
+            if consumed == 0 {
+                break;
+            }
+
+            self.input.drain(..consumed);
+        }
+    }
+
+    // Process the first complete command in the input buffer and return the number of bytes consumed
+    fn process(&mut self) -> usize {
+        if self.input.is_empty() {
+            return 0;
+        }
+
+        match self.mode {
+            ConnectionMode::Legacy => return self.process_legacy(),
+            ConnectionMode::Ascii => return self.process_ascii(),
+            ConnectionMode::Binary => ()
+        }
+
+        // the plain nop sent by the connection verification is answered like an xnop
+        if self.input[0] == 0xC4 {
+            self.reply(&[0x00, 0x00]);
+
+            return 1;
+        }
+
+        // other bytes without the extended character are original P50 commands, P50Xa lines are not accepted
+        if self.input[0] != self.extended_character {
+            return self.process_legacy();
+        }
+
+        if self.input.len() < 2 {
+            return 0;
+        }
+
+        let opcode = self.input[1];
+        let length = match Simulator::parameter_length(opcode) {
+            Some(length) => length,
+            None => {
+                self.reply_code(P50XReply::BadCommand);
+
+                return 2;
+            }
+        };
+
+        if self.input.len() < 2 + length {
+            return 0;
+        }
+
+        let parameters = self.input[2..2 + length].to_vec();
+        self.execute(opcode, &parameters);
+
+        return 2 + length;
+    }
+
+    // Process a textual P50Xa command line and answer with the reply lines followed by the prompt
+    fn process_ascii(&mut self) -> usize {
+        let end = match self.input.iter().position(|data| *data == b'\r' || *data == b'\n') {
+            Some(end) => end,
+            None => return 0
+        };
+
+        let line = String::from_utf8_lossy(&self.input[..end]).to_string();
+
+        let lines = match self.execute_ascii(&line) {
+            Ok(lines) => lines,
+            Err(reply) => vec![error_message(reply)]
+        };
+
+        for line in lines {
+            self.reply(line.as_bytes());
+            self.reply(b"\r");
+        }
+
+        self.reply(b"]");
+
+        return end + 1;
+    }
+
+    // Process an original P50 command, only s88 commands are answered
+    fn process_legacy(&mut self) -> usize {
+        let command = self.input[0];
+
+        match command {
+            0..=31 | legacy::TURNOUT_GREEN | legacy::TURNOUT_RED | 64..=79 => {
+                if self.input.len() < 2 {
+                    return 0;
+                }
+
+                let address = self.input[1];
+
+                match command {
+                    legacy::TURNOUT_GREEN => self.legacy_turnout(address, true),
+                    legacy::TURNOUT_RED => self.legacy_turnout(address, false),
+                    64..=79 => self.legacy_lok_functions(address, command & 0x0F),
+                    _ => self.legacy_lok(address, command)
+                }
+
+                return 2;
+            },
+            legacy::GO => self.set_power(true),
+            legacy::STOP => self.set_power(false),
+            129..=159 => {
+                for module in 1..=command - legacy::S88_MODULES {
+                    self.legacy_s88_module(module);
+                }
+            },
+            193..=223 => self.legacy_s88_module(command - legacy::S88_MODULE),
+            _ => ()
+        }
+
+        return 1;
+    }
+
+    fn legacy_lok(&mut self, address: u8, data: u8) {
+        if address == 0 || address > MAX_LEGACY_LOK_ADDRESS {
+            return;
+        }
+
+        let lok = self.loks.entry(address as u16).or_default();
+        let speed = data & 0x0F;
+
+        lok.light = data & legacy::LOK_FUNCTION != 0;
+
+        if speed == legacy::LOK_CHANGE_DIRECTION {
+            lok.reverse = lok.reverse == false;
+            lok.speed = 0;
+        } else if speed == 0 {
+            lok.speed = 0;
+        } else {
+            // map the 14 speed steps onto the P50X speed range, 1 is the emergency stop
+            lok.speed = speed * 9 + 1;
+        }
+    }
+
+    fn legacy_lok_functions(&mut self, address: u8, functions: u8) {
+        if address == 0 || address > MAX_LEGACY_LOK_ADDRESS {
+            return;
+        }
+
+        let lok = self.loks.entry(address as u16).or_default();
+        lok.functions = (lok.functions & !0x0F) | functions as u32;
+    }
+
+    fn legacy_turnout(&mut self, address: u8, state: bool) {
+        // address 256 is sent as 0
+        let address = if address == 0 {
+            256
+        } else {
+            address as u16
+        };
+
+        self.turnouts.entry(address).or_default().state = state;
+    }
+
+    fn legacy_s88_module(&mut self, module: u8) {
+        let mut contacts = [false; 16];
+
+        for (i, contact) in contacts.iter_mut().enumerate() {
+            *contact = self.sensor(module, i as u8);
+        }
+
+        self.reply(&legacy::contacts_to_bytes(contacts));
+    }
+
+    fn parameter_length(opcode: u8) -> Option<usize> {
+        match opcode {
+            0xA0 | 0xA2 | 0xA5 | 0xA6 | 0xA7 | 0xC4 | 0x93 | 0x99 | 0xC8 | 0xC9 | 0xCA | 0xCB | 0xCE | 0xE4 => Some(0),
+            0xA1 | 0x98 | 0x9C | 0x9E | 0x9F | 0x95 | 0xEA | 0xEE => Some(1),
+            0xA4 | 0x83 | 0x84 | 0x85 | 0x8C | 0x8D | 0x8E | 0x90 | 0x94 | 0x9D | 0xE8 | 0xEB | 0xEC | 0xEF | 0xF0 => Some(2),
+            0xA3 | 0x88 | 0x89 | 0xED | 0xF1 | 0xF3 => Some(3),
+            0x80 | 0x8A | 0xF2 | 0xDD => Some(4),
+            0xDE | 0xDF => Some(5),
+            0x86 => Some(6),
+            _ => None
+        }
+    }
+
+    fn execute(&mut self, opcode: u8, parameters: &[u8]) {
+        match opcode {
+            0xA0 => {
+                for block in self.version.clone() {
+                    self.reply(&[block.len() as u8]);
+                    self.reply(&block);
+                }
+
+                self.reply(&[0x00]);
+            },
+            0xA1 => {
+                self.reply_code(P50XReply::Ok);
+                self.extended_character = parameters[0];
+            },
+            0xA2 => {
+                let status = self.status();
+                self.reply(&[status]);
+            },
+            0xA3 => {
+                let special_option = u16::from_le_bytes([parameters[0], parameters[1]]);
+                self.special_options.insert(special_option, parameters[2]);
+
+                self.reply_code(P50XReply::Ok);
+            },
+            0xA4 => {
+                let special_option = u16::from_le_bytes([parameters[0], parameters[1]]);
+                let value = self.special_options.get(&special_option).copied().unwrap_or(0);
+
+                self.reply_code(P50XReply::Ok);
+                self.reply(&[value]);
+            },
+            0xA5 => {
+                self.stop_locomotives();
+                self.reply_code(P50XReply::Ok);
+            },
+            0xA6 => {
+                self.set_power(false);
+                self.reply_code(P50XReply::Ok);
+            },
+            0xA7 => {
+                self.set_power(true);
+                self.reply_code(P50XReply::Ok);
+            },
+            0xC4 => self.reply_code(P50XReply::Ok),
+            0x98 => self.sensor_status(parameters[0]),
+            0x99 => self.reply_code(P50XReply::Ok),
+            0x9C => {
+                let value = self.s88_parameters.get(&parameters[0]).copied().unwrap_or(0);
+
+                self.reply_code(P50XReply::Ok);
+                self.reply(&[value]);
+            },
+            0x9D => {
+                self.s88_parameters.insert(parameters[0], parameters[1]);
+                self.reply_code(P50XReply::Ok);
+            },
+            0x9E => {
+                let value = Simulator::read_s88_value(&mut self.s88_timers, parameters[0]);
+
+                self.reply_code(P50XReply::Ok);
+                self.reply(&value.to_le_bytes());
+            },
+            0x9F => {
+                let value = Simulator::read_s88_value(&mut self.s88_counters, parameters[0]);
+
+                self.reply_code(P50XReply::Ok);
+                self.reply(&value.to_le_bytes());
+            },
+            0x80 => self.lok(parameters),
+            0x83 => {
+                let address = u16::from_le_bytes([parameters[0], parameters[1]]);
+
+                if address & 0xFF00 != 0 {
+                    self.reply(&[0x00]);
+                } else {
+                    self.reply_code(P50XReply::Ok);
+                }
+            },
+            0x84 => self.lok_status(parameters),
+            0x85 => self.lok_config(parameters),
+            0x86 => self.lok_set_config(parameters),
+            0x88..=0x8A => self.lok_functions(opcode, parameters),
+            0x8C..=0x8E => self.lok_functions_status(opcode, parameters),
+            0x90 => self.turnout(parameters),
+            0x93 => {
+                for turnout in self.turnouts.values_mut() {
+                    turnout.reserved = false;
+                }
+
+                self.reply_code(P50XReply::Ok);
+            },
+            0x94 => self.turnout_status(parameters),
+            0x95 => self.turnout_group(parameters[0]),
+            0xC8 => self.event_status(),
+            0xC9 => self.lok_events(),
+            0xCA => self.turnout_events(),
+            0xCB => self.sensor_events(),
+            0xCE => self.programming_event(),
+            0xE4 => {
+                self.programming_result = None;
+                self.reply_code(P50XReply::Ok);
+            },
+            0xE8..=0xF3 => self.programming(opcode, parameters),
+            0xDD..=0xDF => self.programming_on_main(opcode, parameters),
+            _ => self.reply_code(P50XReply::BadCommand)
+        }
+    }
+
+    fn status(&self) -> u8 {
+        let mut status = 0;
+
+        if self.power {
+            status |= 0x08;
+        }
+
+        if self.halt {
+            status |= 0x10;
+        }
+
+        return status;
+    }
+
+    fn read_s88_value(values: &mut [u16; 16], parameter: u8) -> u16 {
+        let index = (parameter & 0x0F) as usize;
+        let value = values[index];
+
+        if parameter & 0x80 != 0 {
+            values[index] = 0;
+        }
+
+        return value;
+    }
+
+    fn sensor_status(&mut self, module: u8) {
+        if module == 0 || module > MAX_SENSOR_MODULE {
+            self.reply_code(P50XReply::BadParameter);
+
+            return;
+        }
+
+        let data = self.sensors.get(&module).copied().unwrap_or(0);
+
+        self.reply_code(P50XReply::Ok);
+        self.reply(&data.to_le_bytes());
+    }
+
+    fn lok_address(&mut self, parameters: &[u8]) -> Option<u16> {
+        let address = u16::from_le_bytes([parameters[0], parameters[1]]);
+
+        if address == 0 || address > MAX_LOK_ADDRESS {
+            self.reply_code(P50XReply::BadLokParameter);
+
+            return None;
+        }
+
+        return Some(address);
+    }
+
+    // Reply for accepted commands which reports the current power state
+    fn accepted_reply(&self) -> P50XReply {
+        if self.power == false {
+            P50XReply::LokPowerOff
+        } else if self.halt {
+            P50XReply::LokHalt
+        } else {
+            P50XReply::Ok
+        }
+    }
+
+    fn lok(&mut self, parameters: &[u8]) {
+        let address = match self.lok_address(parameters) {
+            Some(address) => address,
+            None => return
+        };
+
+        let speed = parameters[2];
+        let config = parameters[3];
+
+        if speed > 127 {
+            self.reply_code(P50XReply::BadLokParameter);
+
+            return;
+        }
+
+        let lok = self.loks.entry(address).or_default();
+        lok.speed = speed;
+        lok.reverse = config & 0x20 != 0;
+        lok.light = config & 0x10 != 0;
+
+        if config & 0x80 != 0 {
+            lok.functions = (lok.functions & !0x0F) | (config & 0x0F) as u32;
+        }
+
+        let reply = self.accepted_reply();
+        self.reply_code(reply);
+    }
+
+    fn lok_status(&mut self, parameters: &[u8]) {
+        let address = match self.lok_address(parameters) {
+            Some(address) => address,
+            None => return
+        };
+
+        let lok = self.loks.get(&address).copied().unwrap_or_default();
+        let mut config = (lok.functions & 0x0F) as u8;
+
+        if lok.light {
+            config |= 0x10;
+        }
+
+        if lok.reverse {
+            config |= 0x20;
+        }
+
+        // the simulated locomotive reaches its target speed instantly, speed 1 is the emergency stop
+        let real_speed = if lok.speed == 1 || self.power == false || self.halt {
+            0
+        } else {
+            lok.speed
+        };
+
+        self.reply_code(P50XReply::Ok);
+        self.reply(&[lok.speed, config, real_speed]);
+    }
+
+    fn lok_config(&mut self, parameters: &[u8]) {
+        let address = match self.lok_address(parameters) {
+            Some(address) => address,
+            None => return
+        };
+
+        let lok = self.loks.get(&address).copied().unwrap_or_default();
+        let virtual_address = lok.virtual_address.unwrap_or(0xFFFF);
+
+        self.reply_code(P50XReply::Ok);
+        self.reply(&[lok.protocol as u8, lok.speed_steps]);
+        self.reply(&virtual_address.to_le_bytes());
+    }
+
+    fn lok_set_config(&mut self, parameters: &[u8]) {
+        let address = match self.lok_address(parameters) {
+            Some(address) => address,
+            None => return
+        };
+
+        let protocol = XProtocol::from(parameters[2]);
+        let speed_steps = parameters[3];
+        let virtual_address = u16::from_le_bytes([parameters[4], parameters[5]]);
+
+        if parameters[2] > 3 || protocol.supports_speed_steps(speed_steps) == false {
+            self.reply_code(P50XReply::BadParameter);
+
+            return;
+        }
+
+        let virtual_address = if virtual_address == 0xFFFF {
+            None
+        } else if virtual_address == 0 || virtual_address > MAX_LOK_ADDRESS {
+            self.reply_code(P50XReply::BadLokParameter);
+
+            return;
+        } else {
+            Some(virtual_address)
+        };
+
+        let lok = self.loks.entry(address).or_default();
+        lok.protocol = protocol;
+        lok.speed_steps = speed_steps;
+        lok.virtual_address = virtual_address;
+
+        self.reply_code(P50XReply::Ok);
+    }
+
+    fn lok_functions(&mut self, opcode: u8, parameters: &[u8]) {
+        let address = match self.lok_address(parameters) {
+            Some(address) => address,
+            None => return
+        };
+
+        // the third group covers two bytes of functions
+        let (shift, mask, value) = match opcode {
+            0x88 => (0, 0xFF, parameters[2] as u32),
+            0x89 => (8, 0xFF, parameters[2] as u32),
+            _ => (16, 0xFFFF, u16::from_le_bytes([parameters[2], parameters[3]]) as u32)
+        };
+
+        let lok = self.loks.entry(address).or_default();
+        lok.functions = (lok.functions & !(mask << shift)) | (value << shift);
+
+        let reply = self.accepted_reply();
+        self.reply_code(reply);
+    }
+
+    fn lok_functions_status(&mut self, opcode: u8, parameters: &[u8]) {
+        let address = match self.lok_address(parameters) {
+            Some(address) => address,
+            None => return
+        };
+
+        let lok = self.loks.get(&address).copied().unwrap_or_default();
+
+        self.reply_code(P50XReply::Ok);
+
+        match opcode {
+            0x8C => self.reply(&[lok.functions as u8]),
+            0x8D => self.reply(&[(lok.functions >> 8) as u8]),
+            _ => self.reply(&((lok.functions >> 16) as u16).to_le_bytes())
+        }
+    }
+
+    fn turnout_address(&mut self, parameters: &[u8]) -> Option<u16> {
+        let address = u16::from_le_bytes([parameters[0], parameters[1] & 0x07]);
+
+        if address == 0 || address > MAX_TURNOUT_ADDRESS {
+            self.reply_code(P50XReply::BadTurnoutParameter);
+
+            return None;
+        }
+
+        return Some(address);
+    }
+
+    fn turnout(&mut self, parameters: &[u8]) {
+        let address = match self.turnout_address(parameters) {
+            Some(address) => address,
+            None => return
+        };
+
+        let data = parameters[1];
+        let turnout = self.turnouts.entry(address).or_default();
+
+        if turnout.reserved && data & 0x20 == 0 {
+            self.reply_code(P50XReply::LokBusy);
+
+            return;
+        }
+
+        turnout.state = data & 0x80 != 0;
+        turnout.reserved = data & 0x20 != 0;
+
+        if self.power {
+            self.reply_code(P50XReply::Ok);
+        } else {
+            self.reply_code(P50XReply::PowerOff);
+        }
+    }
+
+    fn turnout_status(&mut self, parameters: &[u8]) {
+        let address = match self.turnout_address(parameters) {
+            Some(address) => address,
+            None => return
+        };
+
+        let turnout = self.turnouts.get(&address).copied().unwrap_or_default();
+        let protocol = turnout.protocol as u8;
+        let mut data = ((protocol & 0x02) >> 1) | ((protocol & 0x01) << 3);
+
+        if turnout.reserved {
+            data |= 0x02;
+        }
+
+        if turnout.state {
+            data |= 0x04;
+        }
+
+        self.reply_code(P50XReply::Ok);
+        self.reply(&[data]);
+    }
+
+    fn turnout_group(&mut self, group_address: u8) {
+        if group_address == 0 {
+            self.reply_code(P50XReply::BadParameter);
+
+            return;
+        }
+
+        let mut state = 0;
+        let mut reserved = 0;
+
+        for i in 0..8 {
+            let address = (group_address as u16 - 1) * 8 + i + 1;
+            let turnout = self.turnouts.get(&address).copied().unwrap_or_default();
+
+            if turnout.state {
+                state |= 1 << i;
+            }
+
+            if turnout.reserved {
+                reserved |= 1 << i;
+            }
+        }
+
+        self.reply_code(P50XReply::Ok);
+        self.reply(&[state, reserved]);
+    }
+
+    fn event_status(&mut self) {
+        let mut data = [0u8; 3];
+
+        if self.lok_events.is_empty() == false {
+            data[0] |= 0x01;
+        }
+
+        if self.sensor_events.is_empty() == false {
+            data[0] |= 0x04;
+        }
+
+        if self.power_off_event {
+            data[0] |= 0x08;
+        }
+
+        if self.turnout_events.is_empty() == false {
+            data[0] |= 0x20;
+        }
+
+        if self.status_event {
+            data[1] |= 0x40;
+        }
+
+        if self.programming_result.is_some() {
+            data[2] |= 0x04;
+        }
+
+        self.power_off_event = false;
+        self.status_event = false;
+
+        // each byte signals with its highest bit if another byte follows
+        if data[2] != 0 {
+            data[0] |= 0x80;
+            data[1] |= 0x80;
+
+            self.reply(&data);
+        } else if data[1] != 0 {
+            data[0] |= 0x80;
+
+            self.reply(&data[..2]);
+        } else {
+            self.reply(&data[..1]);
+        }
+    }
+
+    fn lok_events(&mut self) {
+        for address in std::mem::take(&mut self.lok_events) {
+            let lok = self.loks.get(&address).copied().unwrap_or_default();
+            let mut high = ((address >> 8) & 0x3F) as u8;
+
+            if lok.light {
+                high |= 0x40;
+            }
+
+            if lok.reverse {
+                high |= 0x80;
+            }
+
+            let real_speed = if lok.speed == 1 { 0 } else { lok.speed };
+
+            self.reply(&[lok.speed, lok.functions as u8, address as u8, high, real_speed]);
+        }
+
+        self.reply(&[0x80]);
+    }
+
+    fn turnout_events(&mut self) {
+        let events = std::mem::take(&mut self.turnout_events);
+        let count = events.len().min(255);
+
+        self.reply(&[count as u8]);
+
+        for address in &events[..count] {
+            let mut high = ((address >> 8) & 0x07) as u8;
+
+            if self.turnout_state(*address) {
+                high |= 0x80;
+            }
+
+            self.reply(&[*address as u8, high]);
+        }
+    }
+
+    fn sensor_events(&mut self) {
+        for module in std::mem::take(&mut self.sensor_events) {
+            let data = self.sensors.get(&module).copied().unwrap_or(0);
+
+            self.reply(&[module]);
+            self.reply(&data.to_le_bytes());
+        }
+
+        self.reply(&[0x00]);
+    }
+
+    fn programming(&mut self, opcode: u8, parameters: &[u8]) {
+        // register commands take a single byte, all others a cv number
+        let (cv, value_index) = match opcode {
+            0xE8 | 0xEA | 0xEB | 0xEE | 0xEF => (parameters[0] as u16, 1),
+            _ => (u16::from_le_bytes([parameters[0], parameters[1]]), 2)
+        };
+
+        let max = match opcode {
+            0xE8 => 80,
+            0xEA | 0xEB | 0xEE | 0xEF => 8,
+            _ => 1024
+        };
+
+        if cv == 0 || cv > max || ((opcode == 0xF1 || opcode == 0xF2) && parameters[2] > 7) {
+            self.reply_code(P50XReply::BadParameter);
+
+            return;
+        }
+
+        if self.programming_result.is_some() {
+            self.reply_code(P50XReply::ProgrammingBusy);
+
+            return;
+        }
+
+        self.reply_code(P50XReply::Ok);
+
+        if self.programming_decoder == false {
+            self.programming_result = Some((P50XReply::ProgrammingNoAcknowledge, None));
+
+            return;
+        }
+
+        // register 5 maps to cv 29 in register mode
+        let cv = match opcode {
+            0xEA | 0xEB | 0xEE | 0xEF if cv == 5 => 29,
+            _ => cv
+        };
+
+        let current = self.programming_cv(cv);
+
+        let value = match opcode {
+            0xEA | 0xEC | 0xEE | 0xF0 => Some(current),
+            0xF1 => Some((current >> parameters[2]) & 0x01),
+            0xF2 => {
+                let mask = 1 << parameters[2];
+                let value = if parameters[3] != 0 { current | mask } else { current & !mask };
+                self.programming_cvs.insert(cv, value);
+
+                None
+            },
+            _ => {
+                self.programming_cvs.insert(cv, parameters[value_index]);
+
+                None
+            }
+        };
+
+        self.programming_result = Some((P50XReply::Ok, value));
+    }
+
+    fn programming_on_main(&mut self, opcode: u8, parameters: &[u8]) {
+        let address = match self.lok_address(parameters) {
+            Some(address) => address,
+            None => return
+        };
+
+        let cv = u16::from_le_bytes([parameters[2], parameters[3]]);
+
+        if cv == 0 || cv > 1024 {
+            self.reply_code(P50XReply::BadParameter);
+
+            return;
+        }
+
+        if opcode == 0xDD && self.railcom == false {
+            self.reply_code(P50XReply::BadCommand);
+
+            return;
+        }
+
+        if self.power == false {
+            self.reply_code(P50XReply::PowerOff);
+
+            return;
+        }
+
+        let value = self.lok_cvs.entry((address, cv)).or_default();
+
+        match opcode {
+            0xDD => {
+                let value = *value;
+
+                self.reply_code(P50XReply::Ok);
+                self.reply(&[value]);
+            },
+            0xDE => {
+                *value = parameters[4];
+                self.reply_code(P50XReply::Ok);
+            },
+            _ => {
+                let mask = 1 << (parameters[4] & 0x07);
+
+                if parameters[4] & 0x08 != 0 {
+                    *value |= mask;
+                } else {
+                    *value &= !mask;
+                }
+
+                self.reply_code(P50XReply::Ok);
+            }
+        }
+    }
+
+    fn execute_ascii(&mut self, line: &str) -> std::result::Result<Vec<String>, P50XReply> {
+        let fields = fields(line);
+
+        // an empty line is only answered with the prompt
+        if fields.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let command = fields[0].to_uppercase();
+        let arguments = &fields[1..];
+
+        match (command.as_str(), arguments.len()) {
+            ("GO", 0) => self.set_power(true),
+            ("STOP", 0) | (".", 0) => self.set_power(false),
+            ("HALT", 0) => self.stop_locomotives(),
+            ("ZZA1", 0) => self.mode = ConnectionMode::Binary,
+            ("?", 0) => {
+                // the prompt character can not be part of a reply
+                return Ok(vec![
+                    "Go, Stop, Halt, V".to_string(),
+                    "L a (, s, l, f|r (, f1, f2, f3, f4))".to_string(),
+                    "F a (, f1 .. f8), FX a (, f9 .. f16)".to_string(),
+                    "T a (, g|r (, o)), S m, SO n (, v)".to_string()
+                ]);
+            },
+            ("V", 0) => {
+                let version = self.version.first().cloned().unwrap_or_default();
+                let number: Vec<String> = version.iter().skip(1).map(|value| format!("{:02X}", value)).collect();
+                let major = version.first().copied().unwrap_or(0);
+
+                return Ok(vec!["P50X simulator".to_string(), format!("Version {:X}.{}", major, number.join("."))]);
+            },
+            ("SO", 1) => {
+                let special_option = Simulator::ascii_value::<u16>(arguments[0])?;
+                let value = self.special_options.get(&special_option).copied().unwrap_or(0);
+
+                return Ok(vec![format!("SO {} {}", special_option, value)]);
+            },
+            ("SO", 2) => {
+                let special_option = Simulator::ascii_value::<u16>(arguments[0])?;
+                let value = Simulator::ascii_value::<u8>(arguments[1])?;
+
+                self.special_options.insert(special_option, value);
+            },
+            ("S", 1) => {
+                let module = Simulator::ascii_value::<u8>(arguments[0])?;
+
+                if module == 0 || module > MAX_SENSOR_MODULE {
+                    return Err(P50XReply::BadParameter);
+                }
+
+                let contacts: String = (0..16).map(|contact| if self.sensor(module, contact) { '1' } else { '0' }).collect();
+
+                return Ok(vec![format!("S {} {}", module, contacts)]);
+            },
+            ("L", 1) => {
+                let address = Simulator::ascii_lok_address(arguments[0])?;
+                let lok = self.loks.get(&address).copied().unwrap_or_default();
+                let direction = if lok.reverse { "r" } else { "f" };
+                let functions: Vec<String> = (0..4).map(|i| (lok.functions >> i & 1).to_string()).collect();
+
+                return Ok(vec![format!("L {} {} {} {} {}", address, lok.speed, lok.light as u8, direction, functions.join(" "))]);
+            },
+            ("L", 4) | ("L", 8) => {
+                let address = Simulator::ascii_lok_address(arguments[0])?;
+                let speed = Simulator::ascii_value::<u8>(arguments[1])?;
+                let light = Simulator::ascii_flag(arguments[2])?;
+
+                let reverse = match arguments[3] {
+                    "f" | "F" => false,
+                    "r" | "R" => true,
+                    _ => return Err(P50XReply::BadParameter)
+                };
+
+                if speed > 127 {
+                    return Err(P50XReply::BadParameter);
+                }
+
+                let mut functions = None;
+
+                if arguments.len() == 8 {
+                    let mut value = 0;
+
+                    for i in 0..4 {
+                        if Simulator::ascii_flag(arguments[4 + i])? {
+                            value |= 1 << i;
+                        }
+                    }
+
+                    functions = Some(value);
+                }
+
+                let lok = self.loks.entry(address).or_default();
+                lok.speed = speed;
+                lok.reverse = reverse;
+                lok.light = light;
+
+                if let Some(value) = functions {
+                    lok.functions = (lok.functions & !0x0F) | value;
+                }
+
+                return self.ascii_accepted();
+            },
+            ("F", 1) | ("FX", 1) => {
+                let address = Simulator::ascii_lok_address(arguments[0])?;
+                let shift = if command == "F" { 0 } else { 8 };
+                let lok = self.loks.get(&address).copied().unwrap_or_default();
+                let functions: Vec<String> = (0..8).map(|i| (lok.functions >> (shift + i) & 1).to_string()).collect();
+
+                return Ok(vec![format!("{} {} {}", command, address, functions.join(" "))]);
+            },
+            ("F", 9) | ("FX", 9) => {
+                let address = Simulator::ascii_lok_address(arguments[0])?;
+                let shift = if command == "F" { 0 } else { 8 };
+                let mut value = 0;
+
+                for i in 0..8 {
+                    if Simulator::ascii_flag(arguments[1 + i])? {
+                        value |= 1 << i;
+                    }
+                }
+
+                let lok = self.loks.entry(address).or_default();
+                lok.functions = (lok.functions & !(0xFF << shift)) | (value << shift);
+
+                return self.ascii_accepted();
+            },
+            ("T", 1) => {
+                let address = Simulator::ascii_turnout_address(arguments[0])?;
+                let color = if self.turnout_state(address) { "g" } else { "r" };
+
+                return Ok(vec![format!("T {} {}", address, color)]);
+            },
+            ("T", 2) | ("T", 3) => {
+                let address = Simulator::ascii_turnout_address(arguments[0])?;
+
+                let state = match arguments[1] {
+                    "g" | "G" => true,
+                    "r" | "R" => false,
+                    _ => return Err(P50XReply::BadParameter)
+                };
+
+                if arguments.len() == 3 {
+                    Simulator::ascii_flag(arguments[2])?;
+                }
+
+                let turnout = self.turnouts.entry(address).or_default();
+
+                if turnout.reserved {
+                    return Err(P50XReply::LokBusy);
+                }
+
+                turnout.state = state;
+
+                if self.power == false {
+                    return Err(P50XReply::PowerOff);
+                }
+            },
+            ("GO", _) | ("STOP", _) | ("HALT", _) | ("V", _) | ("SO", _) | ("S", _) | ("L", _) | ("F", _) | ("FX", _) | ("T", _) => {
+                return Err(P50XReply::BadParameter);
+            },
+            _ => return Err(P50XReply::BadCommand)
+        }
+
+        return Ok(Vec::new());
+    }
+
+    fn ascii_accepted(&self) -> std::result::Result<Vec<String>, P50XReply> {
+        match self.accepted_reply() {
+            P50XReply::Ok => Ok(Vec::new()),
+            reply => Err(reply)
+        }
+    }
+
+    fn ascii_value<T: FromStr>(value: &str) -> std::result::Result<T, P50XReply> {
+        value.parse::<T>().map_err(|_| P50XReply::BadParameter)
+    }
+
+    fn ascii_flag(value: &str) -> std::result::Result<bool, P50XReply> {
+        match value {
+            "0" => Ok(false),
+            "1" => Ok(true),
+            _ => Err(P50XReply::BadParameter)
+        }
+    }
+
+    fn ascii_lok_address(value: &str) -> std::result::Result<u16, P50XReply> {
+        match value.parse::<u16>() {
+            Ok(address) if address != 0 && address <= MAX_LOK_ADDRESS => Ok(address),
+            _ => Err(P50XReply::BadLokParameter)
+        }
+    }
+
+    fn ascii_turnout_address(value: &str) -> std::result::Result<u16, P50XReply> {
+        match value.parse::<u16>() {
+            Ok(address) if address != 0 && address <= MAX_TURNOUT_ADDRESS => Ok(address),
+            _ => Err(P50XReply::BadTurnoutParameter)
+        }
+    }
+
+    fn programming_event(&mut self) {
+        match self.programming_result.take() {
+            Some((status, Some(value))) => self.reply(&[2, u8::from(status), value]),
+            Some((status, None)) => self.reply(&[1, u8::from(status)]),
+            None => self.reply(&[0xF5])
+        }
+    }
+
+    fn reply_code(&mut self, reply: P50XReply) {
+        self.output.push_back(u8::from(reply));
+    }
+
+    fn reply(&mut self, data: &[u8]) {
+        self.output.extend(data);
+    }
+}
+
+impl Read for Simulator {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // behave like a serial port which did not receive anything in time
+        if self.output.is_empty() {
+            return Err(io::Error::from(io::ErrorKind::TimedOut));
+        }
+
+        let length = buf.len().min(self.output.len());
+        for (i, value) in self.output.drain(..length).enumerate() {
+            buf[i] = value;
+        }
+
+        return Ok(length);
+    }
+}
+
+impl Write for Simulator {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.receive(buf);
+
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return Ok(());
+    }
+}
+
+impl Transport for Simulator {}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::io::{self, Read, Write};
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::Simulator;
+    use crate::reply::P50XReply;
+    use crate::transport::Transport;
+
+    /// Simulator which behaves like a real connection for tests, it can be unplugged, reject commands or send stray
+    /// bytes and keeps the opcodes of all sent commands.
+    pub(crate) struct TestSimulator {
+        pub simulator: Simulator,
+        pub opcodes: Arc<Mutex<Vec<u8>>>,
+        /// Reads time out and writes are dropped while unplugged.
+        pub plugged: Arc<AtomicBool>,
+        /// Number of turnout commands rejected with a full command stack before they reach the simulator.
+        pub rejections: usize,
+        /// Called with each rejection before its reply is sent.
+        pub on_rejection: Option<Box<dyn FnMut() + Send>>,
+        /// Stray bytes in front of the next reply.
+        pub noise: Vec<u8>,
+        rejected: bool
+    }
+
+    impl TestSimulator {
+        pub fn new(simulator: Simulator) -> TestSimulator {
+            TestSimulator {
+                simulator,
+                opcodes: Arc::new(Mutex::new(Vec::new())),
+                plugged: Arc::new(AtomicBool::new(true)),
+                rejections: 0,
+                on_rejection: None,
+                noise: Vec::new(),
+                rejected: false
+            }
+        }
+    }
+
+    impl Read for TestSimulator {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.plugged.load(Ordering::SeqCst) == false {
+                return Err(io::Error::from(io::ErrorKind::TimedOut));
+            }
+
+            let mut data: Vec<u8> = self.noise.drain(..).collect();
+
+            if self.rejected {
+                self.rejected = false;
+                data.push(u8::from(P50XReply::FullTurnoutCommandStack));
+            } else {
+                let mut reply = [0; 256];
+                match self.simulator.read(&mut reply) {
+                    Ok(count) => data.extend_from_slice(&reply[..count]),
+                    Err(err) if data.is_empty() => return Err(err),
+                    Err(_) => ()
+                }
+            }
+
+            let length = data.len().min(buf.len());
+            buf[..length].copy_from_slice(&data[..length]);
+
+            return Ok(length);
+        }
+    }
+
+    impl Write for TestSimulator {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.plugged.load(Ordering::SeqCst) == false {
+                return Ok(buf.len());
+            }
+
+            // each command frame is written at once
+            if let Some(opcode) = buf.get(1) {
+                self.opcodes.lock().unwrap().push(*opcode);
+
+                if *opcode == 0x90 && self.rejections > 0 {
+                    self.rejections -= 1;
+                    self.rejected = true;
+
+                    if let Some(on_rejection) = self.on_rejection.as_mut() {
+                        on_rejection();
+                    }
+
+                    return Ok(buf.len());
+                }
+            }
+
+            self.simulator.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            return Ok(());
+        }
+    }
+
+    impl Transport for TestSimulator {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Device, P50XBinary, XLokOptions, XTurnoutOptions, XTurnoutEvent};
+
+    #[test]
+    fn device_connects_to_simulator() {
+        let mut device = Device::from_transport(Simulator::new()).unwrap();
+
+        device.xnop().unwrap();
+        device.xpower_on().unwrap();
+
+        assert!(device.xstatus().unwrap().power);
+    }
+
+    #[test]
+    fn lok_state_is_kept() {
+        let mut device = Device::from_transport(Simulator::new()).unwrap();
+        device.xpower_on().unwrap();
+
+        let options = XLokOptions {
+            light: true,
+            functions: Some([true, true, false, false]),
+            ..Default::default()
+        };
+        device.xlok(3, -42, options).unwrap();
+        assert_eq!(device.xlok_status(3).unwrap().options.functions, Some([true, true, false, false]));
+
+        device.xfunc(3, [true, true, false, false, true, false, false, false]).unwrap();
+
+        let status = device.xlok_status(3).unwrap();
+        assert_eq!(status.speed, -42);
+        assert_eq!(status.real_speed, -42);
+        assert!(status.options.light);
+
+        let functions = device.xfunc_status(3).unwrap();
+        assert_eq!(functions, [true, true, false, false, true, false, false, false]);
+
+        assert_eq!(device.into_transport().lok_speed(3), -42);
+    }
+
+    #[test]
+    fn turnout_and_sensor_state() {
+        let mut simulator = Simulator::new();
+        simulator.set_power(true);
+        simulator.set_sensor(2, 3, true);
+
+        let mut device = Device::from_transport(simulator).unwrap();
+        device.xturnout(10, true, XTurnoutOptions::default()).unwrap();
+
+        assert!(device.xturnout_status(10).unwrap().state);
+        assert_eq!(device.xturnout_group(2).unwrap()[1], (true, false));
+        assert!(device.xsensor(2).unwrap()[3]);
+    }
+
+    #[test]
+    fn external_changes_are_reported_as_events() {
+        let mut simulator = Simulator::new();
+        simulator.set_power(true);
+        simulator.drive_lok(1234, -10);
+        simulator.switch_turnout(300, true);
+        simulator.set_sensor(4, 0, true);
+
+        let mut device = Device::from_transport(simulator).unwrap();
+
+        let status = device.xevent().unwrap();
+        assert!(status.lok && status.turnout && status.sensor);
+        assert!(status.power_off == false);
+
+        let loks = device.xevt_lok().unwrap();
+        assert_eq!(loks.len(), 1);
+        assert_eq!(loks[0].address, 1234);
+        assert_eq!(loks[0].speed, -10);
+
+        let turnouts = device.xevt_turnout().unwrap();
+        assert_eq!(turnouts, vec![XTurnoutEvent { address: 300, state: true }]);
+
+        let sensors = device.xevt_sensor().unwrap();
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(sensors[0].module, 4);
+        assert!(sensors[0].contacts[0]);
+
+        assert!(device.xevent().unwrap().any() == false);
+    }
+
+    #[test]
+    fn special_options_and_bad_parameters() {
+        let mut device = Device::from_transport(Simulator::new()).unwrap();
+
+        device.xso_set(1, 5).unwrap();
+        assert_eq!(device.xso_get(1).unwrap(), 5);
+
+        match device.xsensor(0) {
+            Err(ref err) if err.reply() == Some(P50XReply::BadParameter) => (),
+            result => panic!("Unexpected result: {:?}", result)
+        }
+    }
+}
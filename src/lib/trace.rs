@@ -0,0 +1,90 @@
+/*
+ * File: trace.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use log::trace;
+
+use super::opcode::NAMES;
+use super::reply::P50XReply;
+
+/// Name of a P50Xb command as used in the protocol documentation.
+pub fn command_name(opcode: u8) -> Option<&'static str> {
+    NAMES.iter().find(|(value, _)| *value == opcode).map(|(_, name)| *name)
+}
+
+/// Bytes as space separated hex values, e.g. `58 80 03 00`.
+pub fn hex(data: &[u8]) -> String {
+    let values: Vec<String> = data.iter().map(|value| format!("{:02X}", value)).collect();
+
+    return values.join(" ");
+}
+
+/// Short description of data sent to a device.
+pub fn describe(data: &[u8], extended_character: u8) -> String {
+    if data.len() >= 2 && data[0] == extended_character {
+        return match command_name(data[1]) {
+            Some(name) => name.to_string(),
+            None => format!("Unknown command 0x{:02X}", data[1])
+        };
+    }
+
+    // ascii commands are always terminated by a carriage return
+    if data.last() == Some(&b'\r') && data.iter().all(|value| value.is_ascii_graphic() || *value == b' ' || *value == b'\r') {
+        return format!("ASCII {:?}", String::from_utf8_lossy(data));
+    }
+
+    return "P50".to_string();
+}
+
+pub(crate) fn outgoing(data: &[u8], extended_character: u8) {
+    if data.is_empty() == false {
+        trace!("> {:<24} {}", hex(data), describe(data, extended_character));
+    }
+}
+
+pub(crate) fn incoming(data: &[u8]) {
+    trace!("< {}", hex(data));
+}
+
+pub(crate) fn reply(reply: P50XReply) {
+    trace!("  Reply: {}", reply);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_described() {
+        assert_eq!(hex(&[0x58, 0x80, 0x03, 0x00]), "58 80 03 00");
+        assert_eq!(describe(&[0x58, 0x80, 0x03, 0x00], 0x58), "XLok");
+        assert_eq!(describe(&[0x78, 0xC4], 0x78), "XNOP");
+        assert_eq!(describe(&[0x58, 0x01], 0x58), "Unknown command 0x01");
+        assert_eq!(describe(b"V\r", 0x58), "ASCII \"V\\r\"");
+        assert_eq!(describe(&[0x60], 0x58), "P50");
+    }
+}
@@ -0,0 +1,76 @@
+/*
+ * File: transport.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+use serialport::{SerialPort, SerialPortSettings};
+
+use super::error::*;
+
+/// Byte stream a `Device` communicates over, e.g. a serial port or a tcp socket.
+pub trait Transport: Read + Write {
+    fn set_timeout(&mut self, _timeout: Duration) -> Result<()> {
+        return Ok(());
+    }
+}
+
+/// Open a serial port with the default P50X settings.
+pub fn open_serial(port_name: &str, baud_rate: u32) -> Result<Box<dyn SerialPort>> {
+    let settings = SerialPortSettings {
+        baud_rate,
+        timeout: Duration::from_millis(1000),
+        ..Default::default()
+    };
+
+    let serial = serialport::open_with_settings(port_name, &settings)?;
+
+    return Ok(serial);
+}
+
+impl Transport for dyn SerialPort {
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        SerialPort::set_timeout(self, timeout)?;
+
+        return Ok(());
+    }
+}
+
+impl Transport for TcpStream {
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.set_read_timeout(Some(timeout))?;
+
+        return Ok(());
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        (**self).set_timeout(timeout)
+    }
+}
@@ -0,0 +1,172 @@
+/*
+ * File: version.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::fmt;
+
+use super::capabilities::Capabilities;
+use super::error::*;
+
+// Capabilities added by each software version, older versions only support the basic commands
+const VERSION_CAPABILITIES: [((u8, u8), Capabilities); 3] = [
+    ((0x01, 0x00), Capabilities::EVENTS
+        .union(Capabilities::PROGRAMMING_TRACK)
+        .union(Capabilities::EXTENDED_FUNCTIONS)
+        .union(Capabilities::S88_TIMERS)
+        .union(Capabilities::ASCII)),
+    ((0x01, 0x50), Capabilities::PROGRAMMING_ON_MAIN.union(Capabilities::LOK_CONFIG)),
+    ((0x02, 0x00), Capabilities::FUNCTIONS_34)
+];
+
+// Software versions the table above is known for, newer ones may have dropped or changed commands
+const KNOWN_VERSIONS: (u8, u8) = (0x01, 0x02);
+
+/// Decoded reply of the `XVer` command.
+///
+/// The first block holds the software version, the second the serial number and the third, if sent, the hardware
+/// revision. All blocks are kept in `blocks` including ones without a known meaning.
+#[derive(Debug, PartialEq, Clone)]
+pub struct VersionInfo {
+    pub major: u8,
+    pub minor: u8,
+    pub serial_number: Option<String>,
+    pub hardware_revision: Option<u8>,
+    pub blocks: Vec<Vec<u8>>
+}
+
+impl VersionInfo {
+    /// Parse the length prefixed blocks terminated by a zero length.
+    pub fn parse(data: &[u8]) -> Result<VersionInfo> {
+        let mut blocks = Vec::new();
+        let mut index = 0;
+
+        loop {
+            let length = match data.get(index) {
+                Some(length) => *length as usize,
+                None => return Err(Error::UnknownResponse(format!("Incomplete version {:?}", data)))
+            };
+
+            if length == 0 {
+                break;
+            }
+
+            match data.get(index + 1..index + 1 + length) {
+                Some(block) => blocks.push(block.to_vec()),
+                None => return Err(Error::UnknownResponse(format!("Incomplete version {:?}", data)))
+            };
+
+            index += 1 + length;
+        }
+
+        let (major, minor) = match blocks.first() {
+            Some(block) if block.len() >= 2 => (block[0], block[1]),
+            Some(block) if block.len() == 1 => (block[0], 0),
+            _ => return Err(Error::UnknownResponse(format!("Missing software version {:?}", data)))
+        };
+
+        let serial_number = blocks.get(1).map(|block| block.iter().map(|value| format!("{:02X}", value)).collect());
+        let hardware_revision = blocks.get(2).and_then(|block| block.first().copied());
+
+        return Ok(VersionInfo {
+            major,
+            minor,
+            serial_number,
+            hardware_revision,
+            blocks
+        });
+    }
+
+    /// Capabilities known for the software version or `None` for unrecognised versions.
+    ///
+    /// Devices of the same version may still differ, use `Device::probe_capabilities` to check the device itself.
+    pub fn capabilities(&self) -> Option<Capabilities> {
+        if self.major < KNOWN_VERSIONS.0 || self.major > KNOWN_VERSIONS.1 {
+            return None;
+        }
+
+        let mut result = Capabilities::empty();
+
+        for (version, capabilities) in VERSION_CAPABILITIES.iter() {
+            if (self.major, self.minor) >= *version {
+                result |= *capabilities;
+            }
+        }
+
+        return Some(result);
+    }
+}
+
+impl fmt::Display for VersionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // the version is binary coded decimal
+        write!(f, "Software version: {:X}.{:02X}", self.major, self.minor)?;
+
+        if let Some(serial_number) = &self.serial_number {
+            write!(f, "\nSerial number: {}", serial_number)?;
+        }
+
+        if let Some(hardware_revision) = self.hardware_revision {
+            write!(f, "\nHardware revision: {}", hardware_revision)?;
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_blocks_are_decoded() {
+        let version = VersionInfo::parse(&[0x02, 0x01, 0x50, 0x05, 0x00, 0x00, 0x12, 0x34, 0x56, 0x00]).unwrap();
+
+        assert_eq!(version.major, 0x01);
+        assert_eq!(version.minor, 0x50);
+        assert_eq!(version.serial_number, Some("0000123456".to_string()));
+        assert_eq!(version.hardware_revision, None);
+        assert_eq!(version.to_string(), "Software version: 1.50\nSerial number: 0000123456");
+
+        let capabilities = version.capabilities().unwrap();
+        assert!(capabilities.contains(Capabilities::PROGRAMMING_ON_MAIN));
+        assert!(capabilities.contains(Capabilities::FUNCTIONS_34) == false);
+
+        assert!(VersionInfo::parse(&[0x02, 0x01]).is_err());
+    }
+
+    #[test]
+    fn unrecognised_versions_have_unknown_capabilities() {
+        let version = VersionInfo::parse(&[0x02, 0x02, 0x10, 0x00]).unwrap();
+        assert_eq!(version.capabilities(), Some(Capabilities::all()));
+
+        let version = VersionInfo::parse(&[0x02, 0x03, 0x00, 0x00]).unwrap();
+        assert_eq!(version.capabilities(), None);
+
+        let version = VersionInfo::parse(&[0x02, 0x00, 0x90, 0x00]).unwrap();
+        assert_eq!(version.capabilities(), None);
+    }
+}
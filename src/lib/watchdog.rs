@@ -0,0 +1,258 @@
+/*
+ * File: watchdog.rs
+ * Date: 17.10.2026
+ * Author: MarkAtk
+ *
+ * MIT License
+ *
+ * Copyright (c) 2026 MarkAtk
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::device::Device;
+use super::error::*;
+use super::monitor::{ConnectionEvent, Event, Subscribers};
+use super::protocol::P50XBinary;
+use super::shared::{Priority, SharedDevice};
+use super::transport::Transport;
+
+type Open<T> = Box<dyn FnMut() -> Result<T> + Send>;
+
+/// Keeps the connection of a shared device alive and opens it again once it is lost.
+///
+/// A XNOP is sent every interval. When it is not answered, the device is marked as disconnected, so all commands
+/// fail with `Error::Disconnected`, and the transport is opened again with `open` every interval until the device
+/// answers again. The device keeps its settings like the timeout and a restarted command station is switched back to
+/// the configured extended character.
+pub struct Watchdog<T: Transport + Send + 'static> {
+    device: SharedDevice<Device<T>>,
+    interval: Duration,
+    running: Arc<AtomicBool>,
+    connected: Arc<AtomicBool>,
+    subscribers: Arc<Mutex<Subscribers>>,
+    open: Option<Open<T>>,
+    handle: Option<JoinHandle<()>>
+}
+
+impl<T: Transport + Send + 'static> Watchdog<T> {
+    pub fn new<F>(device: SharedDevice<Device<T>>, open: F, interval: Duration) -> Watchdog<T> where F: FnMut() -> Result<T> + Send + 'static {
+        return Watchdog {
+            device,
+            interval,
+            running: Arc::new(AtomicBool::new(false)),
+            connected: Arc::new(AtomicBool::new(true)),
+            subscribers: Arc::new(Mutex::new(Subscribers::default())),
+            open: Some(Box::new(open)),
+            handle: None
+        };
+    }
+
+    pub fn start(&mut self) {
+        let mut open = match self.open.take() {
+            Some(open) => open,
+            None => return
+        };
+
+        self.running.store(true, Ordering::SeqCst);
+
+        let device = self.device.clone();
+        let interval = self.interval;
+        let running = self.running.clone();
+        let connected = self.connected.clone();
+        let subscribers = self.subscribers.clone();
+
+        self.handle = Some(thread::spawn(move || {
+            while running.load(Ordering::SeqCst) {
+                let event = if connected.load(Ordering::SeqCst) {
+                    check_connection(&device)
+                } else {
+                    reconnect(&device, &mut open)
+                };
+
+                if let Some(event) = event {
+                    connected.store(event == ConnectionEvent::Connected, Ordering::SeqCst);
+                    Subscribers::publish(&subscribers, &[Event::Connection(event)]);
+                }
+
+                thread::park_timeout(interval);
+            }
+        }));
+    }
+
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.subscribers.lock().unwrap().subscribe()
+    }
+
+    pub fn on_event<F: Fn(&Event) + Send + Sync + 'static>(&self, callback: F) {
+        self.subscribers.lock().unwrap().on_event(callback);
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    pub fn stop(mut self) {
+        self.join();
+    }
+
+    fn join(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.running.store(false, Ordering::SeqCst);
+            handle.thread().unpark();
+
+            let _ = handle.join();
+        }
+    }
+}
+
+impl<T: Transport + Send + 'static> Drop for Watchdog<T> {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+// Send the keepalive, returns an event if the connection was lost
+fn check_connection<T: Transport + Send + 'static>(device: &SharedDevice<Device<T>>) -> Option<ConnectionEvent> {
+    let result = device.execute_with_priority(Priority::Low, |device| {
+        let result = device.xnop();
+
+        // any reply proves the device is still there
+        if let Err(ref err) = result {
+            if err.reply().is_none() {
+                device.disconnect();
+            }
+        }
+
+        return Ok(device.is_connected());
+    });
+
+    match result {
+        Ok(true) => None,
+        _ => Some(ConnectionEvent::Disconnected)
+    }
+}
+
+fn reconnect<T: Transport + Send + 'static>(device: &SharedDevice<Device<T>>, open: &mut Open<T>) -> Option<ConnectionEvent> {
+    let transport = open().ok()?;
+
+    match device.execute(move |device| device.reconnect(transport)) {
+        Ok(_) => Some(ConnectionEvent::Connected),
+        Err(_) => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read, Write};
+
+    use super::*;
+    use crate::sim::Simulator;
+    use crate::sim::testing::TestSimulator;
+
+    #[test]
+    fn lost_connection_is_opened_again() {
+        let transport = TestSimulator::new(Simulator::new());
+        let plugged = transport.plugged.clone();
+        let mut device = Device::from_transport(transport).unwrap();
+        device.xp50xch(0x78).unwrap();
+
+        let mut shared = SharedDevice::new(device);
+
+        let open_plugged = plugged.clone();
+        let open = move || {
+            if open_plugged.load(Ordering::SeqCst) == false {
+                return Err(Error::from(io::Error::from(io::ErrorKind::NotFound)));
+            }
+
+            // the command station keeps its extended character after a restart
+            let mut simulator = Simulator::new();
+            simulator.write_all(&[0x58, 0xA1, 0x78]).unwrap();
+            simulator.read_exact(&mut [0]).unwrap();
+
+            let mut transport = TestSimulator::new(simulator);
+            transport.plugged = open_plugged.clone();
+
+            return Ok(transport);
+        };
+
+        let mut watchdog = Watchdog::new(shared.clone(), open, Duration::from_millis(10));
+        let receiver = watchdog.subscribe();
+        watchdog.start();
+
+        plugged.store(false, Ordering::SeqCst);
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(2)).unwrap(), Event::Connection(ConnectionEvent::Disconnected));
+        assert!(watchdog.is_connected() == false);
+
+        match shared.xnop() {
+            Err(Error::Disconnected) => (),
+            result => panic!("Unexpected result {:?}", result)
+        }
+
+        plugged.store(true, Ordering::SeqCst);
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(2)).unwrap(), Event::Connection(ConnectionEvent::Connected));
+        assert!(watchdog.is_connected());
+
+        shared.xnop().unwrap();
+        watchdog.stop();
+    }
+
+    #[test]
+    fn restarted_device_gets_extended_character_again() {
+        let transport = TestSimulator::new(Simulator::new());
+        let plugged = transport.plugged.clone();
+        let mut device = Device::from_transport(transport).unwrap();
+        device.xp50xch(0x78).unwrap();
+
+        let mut shared = SharedDevice::new(device);
+
+        // the command station starts with the default extended character
+        let open_plugged = plugged.clone();
+        let open = move || {
+            if open_plugged.load(Ordering::SeqCst) == false {
+                return Err(Error::from(io::Error::from(io::ErrorKind::NotFound)));
+            }
+
+            let mut transport = TestSimulator::new(Simulator::new());
+            transport.plugged = open_plugged.clone();
+
+            return Ok(transport);
+        };
+
+        let mut watchdog = Watchdog::new(shared.clone(), open, Duration::from_millis(10));
+        let receiver = watchdog.subscribe();
+        watchdog.start();
+
+        plugged.store(false, Ordering::SeqCst);
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(2)).unwrap(), Event::Connection(ConnectionEvent::Disconnected));
+
+        plugged.store(true, Ordering::SeqCst);
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(2)).unwrap(), Event::Connection(ConnectionEvent::Connected));
+
+        assert_eq!(shared.execute(|device| Ok(device.extended_character())).unwrap(), 0x78);
+        shared.xnop().unwrap();
+        watchdog.stop();
+    }
+}